@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::config::TomlStrategies;
+use crate::sites::Sites;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Model driven dice betting bot")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Bet against the configured site using the trained model.
+    Play(PlayArgs),
+    /// Train a new model and write it to the artifact directory.
+    Train(CommonArgs),
+    /// Paper trade against the configured sites, bets are settled locally.
+    Simulate(SimulateArgs),
+    /// Recompute a roll from its seeds and nonce with the algorithm of `--site`, or audit a
+    /// recorded ledger with `--ledger`, against the site it records unless `--site` is given.
+    Verify(VerifyArgs),
+    /// Convert a trained model into a full precision named record.
+    Export(ExportArgs),
+    /// Print the parsed config.
+    InspectConfig(CommonArgs),
+    /// Manage the encrypted credentials file, unlocked with `CREDENTIALS_PASSPHRASE`.
+    Credentials(CredentialsArgs),
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct CommonArgs {
+    /// Path to the TOML config.
+    #[arg(short, long, default_value = "config.toml")]
+    pub config: PathBuf,
+    /// Directory holding the model and its training config.
+    #[arg(short, long, default_value = "artifacts")]
+    pub artifact_dir: PathBuf,
+    /// Override the site enabled in the config.
+    #[arg(long, value_enum)]
+    pub site: Option<Sites>,
+    /// Override the strategy set in the config.
+    #[arg(long, value_enum)]
+    pub strategy: Option<TomlStrategies>,
+    /// Encrypted credentials file, see the `credentials` command.
+    #[arg(long, default_value = "credentials.enc")]
+    pub credentials: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct StateArgs {
+    /// Directory session snapshots are written to on shutdown.
+    #[arg(long, default_value = "state")]
+    pub state_dir: PathBuf,
+    /// Continue from the snapshots in the state directory.
+    #[arg(long)]
+    pub resume: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct PlayArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    #[command(flatten)]
+    pub state: StateArgs,
+    /// Stop after this many rolls.
+    #[arg(long)]
+    pub rolls: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub struct SimulateArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    #[command(flatten)]
+    pub state: StateArgs,
+    /// Stop after this many rolls.
+    #[arg(long, default_value_t = 1000)]
+    pub rolls: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    /// Audit every bet of a session snapshot, or of a bare ledger file, instead of one roll.
+    #[arg(long)]
+    pub ledger: Option<PathBuf>,
+    /// Revealed server seeds for `--ledger`, one `<hash> <seed>` or `<seed>` per line.
+    #[arg(long, requires = "ledger")]
+    pub seeds: Option<PathBuf>,
+    /// With `--ledger`, one more revealed server seed.
+    #[arg(long, required_unless_present = "ledger")]
+    pub server_seed: Option<String>,
+    #[arg(long, required_unless_present = "ledger")]
+    pub client_seed: Option<String>,
+    #[arg(long, required_unless_present = "ledger")]
+    pub nonce: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    /// Output path for the exported record, defaults to `<artifact_dir>/model_full`.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct CredentialsArgs {
+    /// Encrypted credentials file.
+    #[arg(long, default_value = "credentials.enc")]
+    pub credentials: PathBuf,
+    #[command(subcommand)]
+    pub action: CredentialsAction,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CredentialsAction {
+    /// Store a secret, e.g. `DUCK_DICE_API_KEY`, read from the first line of stdin.
+    Set { name: String },
+    /// Delete a stored secret.
+    Remove { name: String },
+    /// List the names of the stored secrets.
+    List,
+}
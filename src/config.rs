@@ -1,8 +1,63 @@
-use serde::Deserialize;
+use std::fmt;
 
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::credentials::{Credentials, Secret};
 use crate::currency::Currency;
+use crate::sites::Sites;
+
+/// Env var and credentials file names of the site secrets.
+pub const CRYPTO_GAMES_API_KEY: &str = "CRYPTO_GAMES_API_KEY";
+pub const FREEBITCOIN_PASSWORD: &str = "FREEBITCOIN_PASSWORD";
+pub const DUCK_DICE_API_KEY: &str = "DUCK_DICE_API_KEY";
+pub const WINDICE_API_KEY: &str = "WINDICE_API_KEY";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    NoSiteEnabled,
+    MultipleSitesEnabled(Vec<Sites>),
+    UnsupportedCurrency {
+        site: Sites,
+        currency: Currency,
+    },
+    StrategyParams {
+        site: Sites,
+        strategy: TomlStrategies,
+        source: toml::de::Error,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSiteEnabled => write!(
+                f,
+                "no site is enabled, set `enabled = true` on exactly one site or pass --site"
+            ),
+            Self::MultipleSitesEnabled(sites) => write!(
+                f,
+                "only one site can be enabled at a time, found {sites:?}; disable the others or pass --site"
+            ),
+            Self::UnsupportedCurrency { site, currency } => {
+                write!(f, "{currency} is not supported by {site:?}")
+            }
+            Self::StrategyParams {
+                site,
+                strategy,
+                source,
+            } => write!(
+                f,
+                "invalid strategy_params for {strategy:?} on {site:?}: {}",
+                source.message()
+            ),
+        }
+    }
+}
 
-#[derive(Debug, Default, Deserialize)]
+impl std::error::Error for ConfigError {}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, ValueEnum)]
 pub enum TomlStrategies {
     AiFight,
     BlaksRunner,
@@ -11,67 +66,288 @@ pub enum TomlStrategies {
     None,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct CryptoGamesConfig {
     pub enabled: bool,
-    pub api_key: String,
+    /// Prefer `CRYPTO_GAMES_API_KEY` or the credentials file over setting this here.
+    #[serde(default)]
+    pub api_key: Secret,
     pub currency: Currency,
     pub strategy: TomlStrategies,
+    #[serde(default)]
+    pub strategy_params: toml::Table,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct FreeBitcoInConfig {
     pub enabled: bool,
     pub btc_address: String,
-    pub password: String,
+    /// Prefer `FREEBITCOIN_PASSWORD` or the credentials file over setting this here.
+    #[serde(default)]
+    pub password: Secret,
     pub strategy: TomlStrategies,
+    #[serde(default)]
+    pub strategy_params: toml::Table,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct DuckDiceConfig {
     pub enabled: bool,
-    pub api_key: String,
+    /// Prefer `DUCK_DICE_API_KEY` or the credentials file over setting this here.
+    #[serde(default)]
+    pub api_key: Secret,
+    pub currency: Currency,
+    pub strategy: TomlStrategies,
+    #[serde(default)]
+    pub strategy_params: toml::Table,
+}
+
+/// Optional, configs written before Windice was supported don't have this section.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct WindiceConfig {
+    pub enabled: bool,
+    /// Prefer `WINDICE_API_KEY` or the credentials file over setting this here.
+    #[serde(default)]
+    pub api_key: Secret,
     pub currency: Currency,
     pub strategy: TomlStrategies,
+    #[serde(default)]
+    pub strategy_params: toml::Table,
 }
 
-#[derive(Debug, Deserialize)]
+/// A `[[sessions]]` entry, run side by side with the other sessions by `play`.
+///
+/// Credentials come from the site's own section, everything else here overrides it.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SessionConfig {
+    pub name: String,
+    pub site: Sites,
+    /// Settle this session's bets locally, see `sites::paper::PaperSite`.
+    #[serde(default, alias = "fake_betting")]
+    pub paper: bool,
+    pub currency: Option<Currency>,
+    pub strategy: Option<TomlStrategies>,
+    pub strategy_params: Option<toml::Table>,
+}
+
+/// Session limits, a session stops once it crosses any of them.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Stop once the session profit drops to `-stop_loss`.
+    pub stop_loss: Option<f32>,
+    /// Stop once the session profit reaches `take_profit`.
+    pub take_profit: Option<f32>,
+    /// Stop after this many losses in a row.
+    pub max_loss_streak: Option<u64>,
+}
+
+/// How often a session checks its ledger against the site, see `sites::ledger`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReconcileConfig {
+    /// Reconcile after this many bets, never when unset.
+    pub every: Option<u64>,
+    /// Balance drift tolerated before it's flagged.
+    pub max_drift: Option<f32>,
+    /// Pause the session until the config is reloaded once the drift passes `max_drift`.
+    pub pause_on_drift: bool,
+}
+
+/// Runner settings shared by every session, reloaded live when config.toml changes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunnerConfig {
+    /// Paper trade every session, see `sites::paper::PaperSite`.
+    pub paper: bool,
+    pub bet_delay_ms: u64,
+    pub limits: LimitsConfig,
+    pub reconcile: ReconcileConfig,
+    /// Currencies a session moves to, in order, once its own can't cover a bet. Those its site
+    /// doesn't take are skipped.
+    pub fallback_currencies: Vec<Currency>,
+}
+
+impl Default for RunnerConfig {
+    fn default() -> Self {
+        Self {
+            paper: false,
+            bet_delay_ms: 100,
+            limits: LimitsConfig::default(),
+            reconcile: ReconcileConfig::default(),
+            fallback_currencies: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
 pub struct TomlConfig {
+    #[serde(default)]
+    pub runner: RunnerConfig,
     pub crypto_games: CryptoGamesConfig,
     pub freebitcoin: FreeBitcoInConfig,
     pub duck_dice: DuckDiceConfig,
+    #[serde(default)]
+    pub windice: WindiceConfig,
+    #[serde(default)]
+    pub sessions: Vec<SessionConfig>,
 }
 
-pub trait SiteConfig {
-    fn with_api_key(mut self, api_key: String) -> Self
-    where
-        Self: Sized,
-    {
-        self
+/// A single-site config for one running session.
+#[derive(Clone, Debug)]
+pub struct EffectiveSession {
+    pub name: String,
+    pub site: Sites,
+    pub paper: bool,
+    pub config: TomlConfig,
+}
+
+impl TomlConfig {
+    /// Replaces every secret with the one resolved by `credentials`.
+    pub fn resolve_credentials(&mut self, credentials: &Credentials) {
+        self.crypto_games.api_key =
+            credentials.resolve(CRYPTO_GAMES_API_KEY, &self.crypto_games.api_key);
+        self.freebitcoin.password =
+            credentials.resolve(FREEBITCOIN_PASSWORD, &self.freebitcoin.password);
+        self.duck_dice.api_key = credentials.resolve(DUCK_DICE_API_KEY, &self.duck_dice.api_key);
+        self.windice.api_key = credentials.resolve(WINDICE_API_KEY, &self.windice.api_key);
+    }
+
+    /// Returns the single enabled site.
+    pub fn active_site(&self) -> Result<Sites, ConfigError> {
+        let enabled = [
+            (Sites::CryptoGames, self.crypto_games.enabled),
+            (Sites::FreeBitcoIn, self.freebitcoin.enabled),
+            (Sites::DuckDiceIo, self.duck_dice.enabled),
+            (Sites::Windice, self.windice.enabled),
+        ]
+        .into_iter()
+        .filter_map(|(site, enabled)| enabled.then_some(site))
+        .collect::<Vec<Sites>>();
+
+        match enabled.as_slice() {
+            [] => Err(ConfigError::NoSiteEnabled),
+            [site] => Ok(*site),
+            _ => Err(ConfigError::MultipleSitesEnabled(enabled)),
+        }
+    }
+
+    /// Enables `site` and disables every other site.
+    pub fn set_active_site(&mut self, site: Sites) {
+        self.crypto_games.enabled = site == Sites::CryptoGames;
+        self.freebitcoin.enabled = site == Sites::FreeBitcoIn;
+        self.duck_dice.enabled = site == Sites::DuckDiceIo;
+        self.windice.enabled = site == Sites::Windice;
+    }
+
+    /// Returns the strategy and its params configured for `site`.
+    pub fn strategy(&self, site: Sites) -> (TomlStrategies, &toml::Table) {
+        match site {
+            Sites::CryptoGames => (
+                self.crypto_games.strategy,
+                &self.crypto_games.strategy_params,
+            ),
+            Sites::FreeBitcoIn => (self.freebitcoin.strategy, &self.freebitcoin.strategy_params),
+            Sites::DuckDiceIo => (self.duck_dice.strategy, &self.duck_dice.strategy_params),
+            Sites::Windice => (self.windice.strategy, &self.windice.strategy_params),
+        }
+    }
+
+    /// Splits the config into one single-site config per session, a config without
+    /// `[[sessions]]` is a single session named after its enabled site.
+    pub fn effective_sessions(&self) -> Result<Vec<EffectiveSession>, ConfigError> {
+        if self.sessions.is_empty() {
+            let site = self.active_site()?;
+
+            return Ok(vec![EffectiveSession {
+                name: format!("{site:?}"),
+                site,
+                paper: self.runner.paper,
+                config: self.clone(),
+            }]);
+        }
+
+        Ok(self
+            .sessions
+            .iter()
+            .map(|session| EffectiveSession {
+                name: session.name.clone(),
+                site: session.site,
+                paper: self.runner.paper || session.paper,
+                config: self.for_session(session),
+            })
+            .collect())
     }
 
-    fn with_username(mut self) -> Self
+    /// Currency used by the enabled site, `None` for sites with a fixed currency.
+    pub fn currency(&self) -> Option<&Currency> {
+        match self.active_site().ok()? {
+            Sites::CryptoGames => Some(&self.crypto_games.currency),
+            Sites::DuckDiceIo => Some(&self.duck_dice.currency),
+            Sites::Windice => Some(&self.windice.currency),
+            Sites::FreeBitcoIn => None,
+        }
+    }
+
+    /// Returns a config with only `session.site` enabled and the session's overrides applied.
+    pub fn for_session(&self, session: &SessionConfig) -> Self {
+        let mut config = self.clone();
+        config.sessions.clear();
+        config.set_active_site(session.site);
+
+        if let Some(currency) = &session.currency {
+            match session.site {
+                Sites::CryptoGames => config.crypto_games.currency = currency.clone(),
+                Sites::DuckDiceIo => config.duck_dice.currency = currency.clone(),
+                Sites::Windice => config.windice.currency = currency.clone(),
+                Sites::FreeBitcoIn => {}
+            }
+        }
+        if let Some(strategy) = session.strategy {
+            config.set_strategy(strategy);
+        }
+        if let Some(params) = &session.strategy_params {
+            config.crypto_games.strategy_params = params.clone();
+            config.freebitcoin.strategy_params = params.clone();
+            config.duck_dice.strategy_params = params.clone();
+            config.windice.strategy_params = params.clone();
+        }
+
+        config
+    }
+
+    pub fn set_strategy(&mut self, strategy: TomlStrategies) {
+        self.crypto_games.strategy = strategy;
+        self.freebitcoin.strategy = strategy;
+        self.duck_dice.strategy = strategy;
+        self.windice.strategy = strategy;
+    }
+}
+
+pub trait SiteConfig {
+    fn with_api_key(self, _api_key: Secret) -> Self
     where
         Self: Sized,
     {
         self
     }
 
-    fn with_password(mut self) -> Self
+    fn with_username(self, _username: String) -> Self
     where
         Self: Sized,
     {
         self
     }
 
-    fn with_currency(mut self, currency: Currency) -> Self
+    fn with_password(self, _password: Secret) -> Self
     where
         Self: Sized,
     {
         self
     }
 
-    fn with_strategy(mut self, strategy: TomlStrategies) -> Self
+    fn with_currency(self, _currency: Currency) -> Self
     where
         Self: Sized,
     {
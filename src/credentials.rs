@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::path::Path;
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+/// Env var holding the passphrase of the encrypted credentials file.
+pub const PASSPHRASE_VAR: &str = "CREDENTIALS_PASSPHRASE";
+
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+
+/// A credential that never shows up in `Debug` or `Display` output.
+#[derive(Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    /// The only way to read the secret, keep the result out of logs.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            write!(f, "<unset>")
+        } else {
+            write!(f, "<redacted>")
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CredentialsError {
+    Io(std::io::Error),
+    MissingPassphrase,
+    /// Wrong passphrase or a corrupted file, AES-GCM can't tell them apart.
+    Decrypt,
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for CredentialsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "unable to access the credentials file: {err}"),
+            Self::MissingPassphrase => write!(
+                f,
+                "the credentials file is encrypted, set {PASSPHRASE_VAR} to unlock it"
+            ),
+            Self::Decrypt => write!(
+                f,
+                "unable to decrypt the credentials file, wrong passphrase or corrupted file"
+            ),
+            Self::Parse(err) => write!(f, "malformed credentials file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for CredentialsError {}
+
+impl From<std::io::Error> for CredentialsError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for CredentialsError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Parse(value)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct EncryptedFile {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> LessSafeKey {
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &key).unwrap())
+}
+
+/// Resolves secrets from, in order: the environment (including `.env`), the encrypted
+/// credentials file, then the plaintext value in config.toml.
+#[derive(Default)]
+pub struct Credentials {
+    vault: BTreeMap<String, String>,
+}
+
+impl Credentials {
+    /// Loads `.env` into the environment and unlocks `vault_path` if it exists.
+    pub fn load(vault_path: &Path) -> Result<Self, CredentialsError> {
+        let _ = dotenvy::dotenv();
+
+        if !vault_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let passphrase =
+            std::env::var(PASSPHRASE_VAR).map_err(|_| CredentialsError::MissingPassphrase)?;
+
+        Self::open(vault_path, &passphrase)
+    }
+
+    pub fn open(vault_path: &Path, passphrase: &str) -> Result<Self, CredentialsError> {
+        let file: EncryptedFile = serde_json::from_slice(&std::fs::read(vault_path)?)?;
+        let salt = hex::decode(file.salt).map_err(|_| CredentialsError::Decrypt)?;
+        let nonce = hex::decode(file.nonce).map_err(|_| CredentialsError::Decrypt)?;
+        let mut ciphertext = hex::decode(file.ciphertext).map_err(|_| CredentialsError::Decrypt)?;
+
+        let nonce =
+            Nonce::try_assume_unique_for_key(&nonce).map_err(|_| CredentialsError::Decrypt)?;
+        let plaintext = derive_key(passphrase, &salt)
+            .open_in_place(nonce, Aad::empty(), &mut ciphertext)
+            .map_err(|_| CredentialsError::Decrypt)?;
+
+        Ok(Self {
+            vault: serde_json::from_slice(plaintext)?,
+        })
+    }
+
+    /// Encrypts the vault with a fresh salt and nonce.
+    pub fn save(&self, vault_path: &Path, passphrase: &str) -> Result<(), CredentialsError> {
+        let rng = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill(&mut salt).expect("System RNG failed");
+        rng.fill(&mut nonce).expect("System RNG failed");
+
+        let mut in_out = serde_json::to_vec(&self.vault)?;
+        derive_key(passphrase, &salt)
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .map_err(|_| CredentialsError::Decrypt)?;
+
+        let file = EncryptedFile {
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(in_out),
+        };
+        std::fs::write(vault_path, serde_json::to_vec_pretty(&file)?)?;
+
+        Ok(())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.vault.keys()
+    }
+
+    pub fn set(&mut self, name: String, value: Secret) {
+        self.vault.insert(name, value.0);
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.vault.remove(name).is_some()
+    }
+
+    /// Looks `name` up in the environment then the vault, falling back to `config_value`.
+    pub fn resolve(&self, name: &str, config_value: &Secret) -> Secret {
+        if let Ok(value) = std::env::var(name) {
+            return Secret(value);
+        }
+
+        self.vault
+            .get(name)
+            .map(|value| Secret(value.clone()))
+            .unwrap_or_else(|| config_value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vault_round_trips_under_its_passphrase_only() {
+        let vault_path =
+            std::env::temp_dir().join(format!("credentials-{}.json", std::process::id()));
+        let mut credentials = Credentials::default();
+        credentials.set(
+            "CREDENTIALS_TEST_API_KEY".to_string(),
+            Secret::new("hunter2".to_string()),
+        );
+        credentials.save(&vault_path, "passphrase").unwrap();
+
+        let opened = Credentials::open(&vault_path, "passphrase");
+        let wrong = Credentials::open(&vault_path, "wrong");
+        std::fs::remove_file(&vault_path).unwrap();
+
+        let opened = opened.unwrap();
+        assert_eq!(
+            opened.names().collect::<Vec<&String>>(),
+            ["CREDENTIALS_TEST_API_KEY"]
+        );
+        assert_eq!(
+            opened
+                .resolve("CREDENTIALS_TEST_API_KEY", &Secret::default())
+                .expose(),
+            "hunter2"
+        );
+        assert!(matches!(wrong, Err(CredentialsError::Decrypt)));
+    }
+
+    #[test]
+    fn secrets_are_redacted() {
+        let secret = Secret::new("hunter2".to_string());
+        assert_eq!(secret.to_string(), "<redacted>");
+        assert_eq!(format!("{secret:?}"), "<redacted>");
+        assert_eq!(format!("{:?}", Secret::default()), "<unset>");
+        assert_eq!(secret.expose(), "hunter2");
+    }
+}
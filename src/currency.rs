@@ -1,8 +1,9 @@
-use serde::Deserialize;
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
 
 use crate::sites::Sites;
 
-#[derive(Clone, Debug, Default, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub enum Currency {
     XRP,
     DECOY,
@@ -97,49 +98,60 @@ impl Currency {
     }
 }
 
-impl ToString for Currency {
-    fn to_string(&self) -> String {
-        match self {
-            Self::XRP => "XRP".to_string(),
-            Self::DECOY => "DECOY".to_string(),
-            Self::USDT => "USDT".to_string(),
-            Self::BTC => "BTC".to_string(),
-            Self::LTC => "LTC".to_string(),
-            Self::TRX => "TRX".to_string(),
-            Self::DOGE => "DOGE".to_string(),
-            Self::ETH => "ETH".to_string(),
-            Self::XLM => "XLM".to_string(),
-            Self::BCH => "BCH".to_string(),
-            Self::BNB => "BNB".to_string(),
-            Self::SHIB => "SHIB".to_string(),
-            Self::USDC => "USDC".to_string(),
-            Self::ADA => "ADA".to_string(),
-            Self::DASH => "DASH".to_string(),
-            Self::SOL => "SOL".to_string(),
-            Self::ATOM => "ATOM".to_string(),
-            Self::ETC => "ETC".to_string(),
-            Self::XMR => "XMR".to_string(),
-            Self::EOS => "EOS".to_string(),
-            Self::BTTC => "BTTC".to_string(),
-            Self::POL => "POL".to_string(),
-            Self::DOT => "DOT".to_string(),
-            Self::ZEC => "ZEC".to_string(),
-            Self::RVN => "RVN".to_string(),
-            Self::LINK => "LINK".to_string(),
-            Self::DAI => "DAI".to_string(),
-            Self::TUSD => "TUSD".to_string(),
-            Self::AVAX => "AVAX".to_string(),
-            Self::NEAR => "NEAR".to_string(),
-            Self::ZEN => "ZEN".to_string(),
-            Self::AAVE => "AAVE".to_string(),
-            Self::NOT => "NOT".to_string(),
-            Self::ENA => "ENA".to_string(),
-            Self::UNI => "UNI".to_string(),
-            Self::TON => "TON".to_string(),
-            Self::TRUMP => "TRUMP".to_string(),
-            Self::FDUSD => "FDUSD".to_string(),
-            Self::WBTC => "WBTC".to_string(),
-            Self::CAD => "CAD".to_string(),
-        }
+impl std::str::FromStr for Currency {
+    type Err = serde::de::value::Error;
+
+    /// Parses a symbol the way config.toml spells it, e.g. `BTC`.
+    fn from_str(symbol: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(symbol.into_deserializer())
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Self::XRP => "XRP",
+            Self::DECOY => "DECOY",
+            Self::USDT => "USDT",
+            Self::BTC => "BTC",
+            Self::LTC => "LTC",
+            Self::TRX => "TRX",
+            Self::DOGE => "DOGE",
+            Self::ETH => "ETH",
+            Self::XLM => "XLM",
+            Self::BCH => "BCH",
+            Self::BNB => "BNB",
+            Self::SHIB => "SHIB",
+            Self::USDC => "USDC",
+            Self::ADA => "ADA",
+            Self::DASH => "DASH",
+            Self::SOL => "SOL",
+            Self::ATOM => "ATOM",
+            Self::ETC => "ETC",
+            Self::XMR => "XMR",
+            Self::EOS => "EOS",
+            Self::BTTC => "BTTC",
+            Self::POL => "POL",
+            Self::DOT => "DOT",
+            Self::ZEC => "ZEC",
+            Self::RVN => "RVN",
+            Self::LINK => "LINK",
+            Self::DAI => "DAI",
+            Self::TUSD => "TUSD",
+            Self::AVAX => "AVAX",
+            Self::NEAR => "NEAR",
+            Self::ZEN => "ZEN",
+            Self::AAVE => "AAVE",
+            Self::NOT => "NOT",
+            Self::ENA => "ENA",
+            Self::UNI => "UNI",
+            Self::TON => "TON",
+            Self::TRUMP => "TRUMP",
+            Self::FDUSD => "FDUSD",
+            Self::WBTC => "WBTC",
+            Self::CAD => "CAD",
+        };
+
+        f.write_str(symbol)
     }
 }
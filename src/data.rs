@@ -94,7 +94,8 @@ impl<B: Backend> Batcher<B, BetResultCsvRecord, BetBatch<B>> for BetBatcher<B> {
             .flat_map(|itm| {
                 let mut arr = [(-1f32).elem::<B::FloatElem>(); 100];
                 if let Some(itm) = itm.last() {
-                    arr[itm.next_number as usize / 100] = 1f32.elem::<B::FloatElem>();
+                    // freebitco.in rolls run up to 10000 inclusive.
+                    arr[(itm.next_number as usize / 100).min(99)] = 1f32.elem::<B::FloatElem>();
                 }
                 arr
             })
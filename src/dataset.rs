@@ -3,11 +3,12 @@ use serde::{Deserialize, Serialize};
 
 use lazy_static::lazy_static;
 use rand::Rng;
-use ring::hmac;
 use ring::rand::{SecureRandom, SystemRandom};
-use sha2::{Digest, Sha256};
 use std::sync::Mutex;
 
+use crate::sites::provably_fair::{FreeBitcoInRoll, RollAlgorithm};
+use crate::sites::{BetKind, Sites};
+
 lazy_static! {
     pub static ref SERVER_STORAGE: Mutex<FakeServerStorage> =
         Mutex::new(FakeServerStorage::default());
@@ -45,39 +46,28 @@ pub struct BetResultCsvRecord {
     pub duplicate_rolls: Vec<u32>,
 }
 
-/// Returns: (rolled_number, server_seed, nonce)
+/// Rolls with freebitco.in's algorithm under a fresh server seed.
+///
+/// Returns: (rolled_number, server_seed_hash, client_seed, nonce)
 pub fn gen_fake_bet(
     server_storage: &mut FakeServerStorage,
-    client_seed: &str,
+    _client_seed: &str,
     nonce: u64,
 ) -> (u32, String, String, u64) {
     let sys_random = SystemRandom::new();
 
-    let mut server_seed = [0u8; 64];
+    let mut server_seed = [0u8; 32];
     sys_random.fill(&mut server_seed).unwrap();
-    let mut hasher = Sha256::new();
-    hasher.update(server_seed);
-    let result = hasher.finalize();
-    let server_seed_hash = hex::encode(result);
+    let server_seed = hex::encode(server_seed);
+    let server_seed_hash = FreeBitcoInRoll.server_seed_hash(&server_seed);
 
-    let client_seed: String = rand::thread_rng()
+    let client_seed: String = rand::rng()
         .sample_iter(rand::distr::Alphanumeric)
-        .take(rand::thread_rng().gen_range(0..64))
+        .take(rand::rng().random_range(0..64))
         .map(char::from)
         .collect();
 
-    let mut combined_seed = Vec::new();
-    combined_seed.extend_from_slice(&server_seed);
-    combined_seed.extend_from_slice(client_seed.as_bytes());
-    combined_seed.extend_from_slice(&nonce.to_be_bytes());
-
-    let key = hmac::Key::new(hmac::HMAC_SHA256, &server_seed);
-    let tag = hmac::sign(&key, &combined_seed);
-
-    let random_bytes = &tag.as_ref()[..4];
-    let random_u32 = u32::from_le_bytes(random_bytes.try_into().unwrap());
-
-    let number = random_u32 % 10_000;
+    let number = FreeBitcoInRoll.roll(&server_seed, &client_seed, nonce);
 
     (
         number,
@@ -88,7 +78,7 @@ pub fn gen_fake_bet(
 }
 
 pub fn free_bitcoin_fake_bet(
-    high: bool,
+    kind: BetKind,
     client_seed: &str,
     _stake: f32,
     multiplier: f32,
@@ -108,9 +98,18 @@ pub fn free_bitcoin_fake_bet(
     server_storage.current_roll = server_storage.next_roll;
     server_storage.next_roll = rolled_number;
 
-    let target = (10_000. * ((99.95 / multiplier) / 100.)) as u32;
-    let result = (high && server_storage.current_roll > (10_000 - target))
-        || (!high && server_storage.current_roll < target);
+    let result = match kind.range_chance() {
+        Some(_) => kind.wins(
+            server_storage.current_roll,
+            0.,
+            Sites::FreeBitcoIn.max_roll(),
+        ),
+        None => {
+            let target = (10_000. * ((99.95 / multiplier) / 100.)) as u32;
+            (kind == BetKind::Over && server_storage.current_roll > (10_000 - target))
+                || (kind == BetKind::Under && server_storage.current_roll < target)
+        }
+    };
 
     let mut record = BetResultCsvRecord {
         result,
@@ -162,7 +161,7 @@ impl BetResultsDataset {
 impl Dataset<BetResultCsvRecord> for BetResultsDataset {
     fn get(&self, index: usize) -> Option<BetResultCsvRecord> {
         Some(free_bitcoin_fake_bet(
-            true,
+            BetKind::Over,
             "lYypIPVEgzvCflWF",
             1e-8,
             2.,
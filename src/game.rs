@@ -0,0 +1,544 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use burn::prelude::*;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::currency::Currency;
+use crate::data;
+use crate::model::Model;
+use crate::reload::LiveSettings;
+use crate::sites::{BetError, BetOutcome, Site};
+use crate::state::{self, SessionSnapshot};
+
+/// Failed bets in a row a session survives before it stops.
+const MAX_RETRIES: u32 = 5;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Bets between snapshots, so a crash loses at most this much of the ledger.
+const SAVE_EVERY: u64 = 500;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SessionStats {
+    pub rolls: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub wagered: f32,
+    #[serde(default)]
+    pub loss_streak: u64,
+}
+
+pub struct Game<B: Backend> {
+    pub name: String,
+    pub stats: SessionStats,
+    confidence: f32,
+    seed: u64,
+    site: Box<dyn Site>,
+    model: Arc<Mutex<Model<B>>>,
+    device: B::Device,
+    prediction: f32,
+    initialized: bool,
+    state_dir: Option<PathBuf>,
+    resume: bool,
+    settings: watch::Receiver<LiveSettings>,
+    /// Bets placed since the ledger was last reconciled.
+    unreconciled: u64,
+}
+
+impl<B: Backend> Game<B> {
+    pub fn new(
+        name: String,
+        site: Box<dyn Site>,
+        model: Arc<Mutex<Model<B>>>,
+        device: B::Device,
+        seed: u64,
+        settings: watch::Receiver<LiveSettings>,
+    ) -> Self {
+        Self {
+            name,
+            stats: SessionStats::default(),
+            confidence: 0.,
+            seed,
+            site,
+            model,
+            device,
+            prediction: 0.,
+            initialized: false,
+            state_dir: None,
+            resume: false,
+            settings,
+            unreconciled: 0,
+        }
+    }
+
+    /// Snapshot the session into `state_dir` when it stops, restoring the last one first if
+    /// `resume` is set.
+    pub fn with_state_dir(mut self, state_dir: PathBuf, resume: bool) -> Self {
+        self.state_dir = Some(state_dir);
+        self.resume = resume;
+
+        self
+    }
+
+    pub fn site(&self) -> &dyn Site {
+        self.site.as_ref()
+    }
+
+    async fn bet(&mut self) -> Result<(), BetError> {
+        if !self.initialized {
+            B::seed(self.seed);
+            self.initialized = true;
+        }
+        let bet_result = self.site.do_bet(self.prediction, self.confidence).await?;
+
+        self.stats.rolls += 1;
+        self.stats.wagered += self.site.get_current_bet();
+        if bet_result.result {
+            self.stats.wins += 1;
+            self.stats.loss_streak = 0;
+            self.site.on_win(&bet_result);
+            self.print_res(&bet_result, true);
+        } else {
+            self.stats.losses += 1;
+            self.stats.loss_streak += 1;
+            self.site.on_lose(&bet_result);
+            self.print_res(&bet_result, false);
+        }
+
+        let history = self.site.get_history();
+        let history_size = self.site.get_history_size();
+        // Get server seed hash next roll and convert it to a tensor of shape (-1, 256).
+        if history.len() >= history_size {
+            let inputs_hash = history
+                .iter()
+                .flat_map(|itm| {
+                    let mut vals = itm
+                        .hash_next_roll
+                        .chars()
+                        .flat_map(|chr| {
+                            let value = chr.to_digit(16).unwrap_or(0);
+                            (0..4)
+                                .rev()
+                                .map(move |i| ((value >> i) & 1).elem::<B::FloatElem>())
+                        })
+                        .collect::<Vec<B::FloatElem>>();
+
+                    vals.resize(256, 0f32.elem::<B::FloatElem>());
+
+                    vals.append(
+                        &mut itm
+                            .hash_previous_roll
+                            .chars()
+                            .flat_map(|chr| {
+                                let value = chr.to_digit(16).unwrap_or(0);
+                                (0..4)
+                                    .rev()
+                                    .map(move |i| ((value >> i) & 1).elem::<B::FloatElem>())
+                            })
+                            .collect::<Vec<B::FloatElem>>(),
+                    );
+
+                    vals.resize(512, 0f32.elem::<B::FloatElem>());
+
+                    vals.append(
+                        &mut itm
+                            .client_seed
+                            .chars()
+                            .flat_map(|chr| {
+                                let value = chr.to_digit(16).unwrap_or(0);
+                                (0..4)
+                                    .rev()
+                                    .map(move |i| ((value >> i) & 1).elem::<B::FloatElem>())
+                            })
+                            .collect::<Vec<B::FloatElem>>(),
+                    );
+
+                    vals.resize(768, 0f32.elem::<B::FloatElem>());
+
+                    vals.append(
+                        &mut (0..32)
+                            .map(|i| ((itm.nonce >> i) & 1).elem::<B::FloatElem>())
+                            .collect::<Vec<B::FloatElem>>(),
+                    );
+
+                    vals.resize(1024, 0f32.elem::<B::FloatElem>());
+
+                    vals
+                })
+                .collect::<Vec<B::FloatElem>>();
+
+            let hash_data = TensorData::new(
+                inputs_hash,
+                [history.len() / history_size, history_size, 4, 256],
+            );
+            let hash_data: Tensor<B, 4> =
+                Tensor::from(hash_data.convert::<B::FloatElem>()).to_device(&self.device);
+
+            let output = self.model.lock().unwrap().forward(data::BetBatch {
+                inputs: hash_data,
+                targets: Tensor::zeros(Shape::new([1, 1]), &self.device),
+            });
+            let predicted_output = output
+                .clone()
+                .argmax(1)
+                .into_data()
+                .to_vec::<i32>()
+                .unwrap();
+            let predicted_output = predicted_output[0];
+            let confidence = output.clone().into_data().to_vec::<f32>().unwrap()
+                [predicted_output as usize]
+                * 100.;
+            // let predicted = (predicted_output[0] + 1.) * 10000. / 2.;
+            // let predicted = (((predicted - 4500.) / (5500. - 4500.)) * (10000. - 0.)) + 0.;
+
+            self.confidence = confidence;
+            self.prediction = predicted_output as f32 * 100.;
+        }
+
+        Ok(())
+    }
+
+    /// Bets until `rolls` is reached, an error occurs or `shutdown` is set. A bet in flight is
+    /// always finished before the session is snapshotted.
+    pub async fn run(
+        &mut self,
+        rolls: Option<u64>,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.site.login().await?;
+        if self.resume {
+            self.restore()?;
+        }
+
+        let mut result = Ok(());
+        let mut failures = 0;
+        while !*shutdown.borrow() && rolls.is_none_or(|rolls| self.site.get_rolls() < rolls) {
+            self.apply_settings();
+            let mut delay = self.settings.borrow().bet_delay;
+            match self.bet().await {
+                Ok(()) => {
+                    failures = 0;
+                    self.unreconciled += 1;
+                    if self.site.get_rolls().is_multiple_of(SAVE_EVERY) {
+                        self.checkpoint();
+                    }
+                }
+                Err(err) => {
+                    failures += 1;
+                    match self.recover(err, failures).await {
+                        Ok(retry_in) => delay = retry_in,
+                        Err(err) => {
+                            result = Err(err);
+                            break;
+                        }
+                    }
+                }
+            }
+            if let Some(limit) = self.limit_reached() {
+                println!("{}", format!("[{}] Stopping, {limit}", self.name).yellow());
+                break;
+            }
+            if self.reconcile_due() && self.reconcile().await {
+                self.pause(&mut shutdown).await;
+                continue;
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+
+        // Audits still running would be lost with the session.
+        if let Err(err) = self.site.finish_audits().await {
+            eprintln!("{}", format!("[{}] [ALERT] {err}", self.name).red().bold());
+            if result.is_ok() {
+                result = Err(err);
+            }
+        }
+        self.save()?;
+
+        Ok(result?)
+    }
+
+    /// Decides whether a failed bet is worth retrying and returns how long to wait first,
+    /// `failures` counts the failed bets in a row. Each request was already retried by
+    /// `sites::request`, bets only where the site can't have taken them, so this backs off
+    /// between bets: after failures the request layer left alone or retried to no avail.
+    async fn recover(&mut self, err: BetError, failures: u32) -> Result<Duration, BetError> {
+        if failures > MAX_RETRIES {
+            return Err(err);
+        }
+
+        let backoff = RETRY_DELAY * 2u32.pow(failures - 1);
+        let retry_in = match &err {
+            BetError::EmptyReply => backoff,
+            BetError::RateLimited { retry_after } => retry_after.unwrap_or(backoff),
+            BetError::HttpStatus(status) if status.is_server_error() => backoff,
+            BetError::ReqwestError(source) if source.is_connect() || source.is_timeout() => backoff,
+            // The site stayed down through the request's own retries, or asked for a longer wait
+            // than they take.
+            BetError::RetriesExhausted { last, .. } => match **last {
+                BetError::RateLimited {
+                    retry_after: Some(retry_after),
+                } => retry_after,
+                _ => backoff,
+            },
+            BetError::AuthExpired => {
+                println!(
+                    "{}",
+                    format!("[{}] {err}, logging in again", self.name).yellow()
+                );
+                self.site.reauthenticate().await?;
+
+                return Ok(Duration::ZERO);
+            }
+            BetError::InsufficientBalance { .. } => return self.fall_back(err).await,
+            BetError::UnfairRolls { .. } => {
+                eprintln!("{}", format!("[{}] [ALERT] {err}", self.name).red().bold());
+
+                return Err(err);
+            }
+            _ => return Err(err),
+        };
+
+        println!(
+            "{}",
+            format!(
+                "[{}] {err}, retrying in {:.1}s",
+                self.name,
+                retry_in.as_secs_f32()
+            )
+            .yellow()
+        );
+
+        Ok(retry_in)
+    }
+
+    /// Moves to the first fallback currency whose balance covers a minimum bet, `err` is handed
+    /// back when there is none.
+    async fn fall_back(&mut self, err: BetError) -> Result<Duration, BetError> {
+        let from = self.site.get_symbol();
+        let fallbacks = self.settings.borrow().fallback_currencies.clone();
+
+        for currency in fallbacks
+            .iter()
+            .filter(|currency| currency.to_string() != from)
+        {
+            match self.site.switch_currency(currency).await {
+                Ok(()) => {}
+                Err(BetError::UnsupportedCurrency { .. }) => continue,
+                Err(switch_err) => {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "[{}] Unable to switch to {currency}: {switch_err}",
+                            self.name
+                        )
+                        .red()
+                    );
+                    continue;
+                }
+            }
+            if self.site.get_balance() >= self.site.capabilities().min_bet {
+                println!(
+                    "{}",
+                    format!("[{}] {err}, switching from {from} to {currency}", self.name).yellow()
+                );
+
+                return Ok(Duration::ZERO);
+            }
+        }
+
+        if self.site.get_symbol() != from {
+            if let Ok(currency) = from.parse::<Currency>() {
+                self.site.switch_currency(&currency).await?;
+            }
+        }
+
+        Err(err)
+    }
+
+    /// Hands reloaded strategy params to the site, the delay and limits are read per bet.
+    fn apply_settings(&mut self) {
+        if !self.settings.has_changed().unwrap_or(false) {
+            return;
+        }
+
+        let params = self.settings.borrow_and_update().strategy_params.clone();
+        if let Err(err) = self.site.set_strategy_params(params) {
+            eprintln!(
+                "{}",
+                format!("[{}] Keeping the old strategy params: {err}", self.name).red()
+            );
+        }
+    }
+
+    fn reconcile_due(&self) -> bool {
+        self.settings
+            .borrow()
+            .reconcile
+            .every
+            .is_some_and(|every| self.unreconciled >= every)
+    }
+
+    /// Checks the ledger against the site and reports what it found, returns whether the
+    /// drift calls for a pause. A failed check is reported and betting goes on.
+    async fn reconcile(&mut self) -> bool {
+        self.unreconciled = 0;
+        let reconcile = self.settings.borrow().reconcile.clone();
+
+        let report = match self.site.reconcile().await {
+            Ok(report) => report,
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    format!("[{}] Reconciliation failed: {err}", self.name).yellow()
+                );
+                return false;
+            }
+        };
+        let drifted = reconcile
+            .max_drift
+            .is_some_and(|max_drift| report.drift().abs() > max_drift);
+        let line = format!("[{}] Reconciled {report}", self.name);
+        if drifted || !report.is_clean() {
+            println!("{}", line.red());
+        } else {
+            println!("{line}");
+        }
+
+        drifted && reconcile.pause_on_drift
+    }
+
+    /// Waits for a config reload or shutdown, whichever comes first.
+    async fn pause(&mut self, shutdown: &mut watch::Receiver<bool>) {
+        println!(
+            "{}",
+            format!(
+                "[{}] Paused on balance drift, reload the config to resume",
+                self.name
+            )
+            .yellow()
+        );
+
+        // A clone so the reload is still pending for `apply_settings`.
+        let mut settings = self.settings.clone();
+        tokio::select! {
+            Ok(()) = settings.changed() => {}
+            _ = shutdown.changed() => {}
+        }
+    }
+
+    fn limit_reached(&self) -> Option<String> {
+        let limits = &self.settings.borrow().limits;
+        let profit = self.site.get_profit();
+
+        if let Some(stop_loss) = limits.stop_loss.filter(|stop_loss| profit <= -stop_loss) {
+            return Some(format!("stop loss of {stop_loss:.8} reached"));
+        }
+        if let Some(take_profit) = limits
+            .take_profit
+            .filter(|take_profit| profit >= *take_profit)
+        {
+            return Some(format!("take profit of {take_profit:.8} reached"));
+        }
+        if let Some(max_loss_streak) = limits
+            .max_loss_streak
+            .filter(|max_loss_streak| self.stats.loss_streak >= *max_loss_streak)
+        {
+            return Some(format!("{max_loss_streak} losses in a row"));
+        }
+
+        None
+    }
+
+    fn snapshot(&self) -> SessionSnapshot {
+        SessionSnapshot {
+            name: self.name.clone(),
+            stats: self.stats.clone(),
+            prediction: self.prediction,
+            confidence: self.confidence,
+            site: self.site.save_state(),
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(state_dir) = &self.state_dir else {
+            return Ok(());
+        };
+
+        state::save(state_dir, &self.snapshot())?;
+        println!("[{}] Saved session to {}", self.name, state_dir.display());
+
+        Ok(())
+    }
+
+    /// Snapshots the session mid-run, a failure is reported and betting goes on.
+    fn checkpoint(&self) {
+        let Some(state_dir) = &self.state_dir else {
+            return;
+        };
+        if let Err(err) = state::save(state_dir, &self.snapshot()) {
+            eprintln!(
+                "{}",
+                format!("[{}] Unable to save session: {err}", self.name).yellow()
+            );
+        }
+    }
+
+    fn restore(&mut self) -> std::io::Result<()> {
+        let Some(state_dir) = &self.state_dir else {
+            return Ok(());
+        };
+        let Some(snapshot) = state::load(state_dir, &self.name)? else {
+            println!("[{}] No saved session, starting fresh", self.name);
+            return Ok(());
+        };
+
+        self.site.load_state(snapshot.site)?;
+        self.stats = snapshot.stats;
+        self.prediction = snapshot.prediction;
+        self.confidence = snapshot.confidence;
+        println!("[{}] Resumed at roll #{}", self.name, self.site.get_rolls());
+
+        Ok(())
+    }
+
+    fn print_res(&self, bet_result: &BetOutcome, win: bool) {
+        let profit_str = &format!("Profit: {:.8}", self.site.get_profit());
+        let profit_str = if self.site.get_profit() > 0. {
+            profit_str.green()
+        } else {
+            profit_str.red()
+        };
+
+        let golden_roll = if bet_result.number > 9900 || bet_result.number < 100 {
+            (&format!("{: <5}", bet_result.number)).yellow()
+        } else {
+            format!("{: <5}", bet_result.number).normal()
+        };
+
+        let output_str = &format!(
+            "[{}] #{: >6} || Balance: {:0>.8} || Roll: {: <5} || Multiplier: {: <6.2} || Wagered: {:.8} || Predicted: {: <5.0} || Confidence: {: <2.2} || {}",
+            self.name,
+            self.site.get_rolls(),
+            self.site.get_balance(),
+            golden_roll,
+            self.site.get_current_multiplier(),
+            self.site.get_current_bet(),
+            self.prediction,
+            self.confidence,
+            profit_str,
+        );
+        let output_str = if win {
+            output_str.green()
+        } else {
+            output_str.red()
+        };
+
+        println!("{output_str}");
+    }
+}
@@ -1,243 +1,301 @@
 #![recursion_limit = "256"]
 
+pub mod cli;
 pub mod config;
+pub mod credentials;
 pub mod currency;
 pub mod data;
 pub mod dataset;
+pub mod game;
 pub mod inference;
 pub mod model;
+pub mod reload;
+pub mod runner;
 pub mod sites;
+pub mod state;
 pub mod strategies;
 pub mod training;
 pub mod util;
+pub mod validate;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::ExitCode;
 
 use burn::{
-    backend::{
-        wgpu::{Wgpu, WgpuDevice},
-        Vulkan,
-    },
+    backend::{wgpu::WgpuDevice, Autodiff, Vulkan},
+    optim::AdamConfig,
     prelude::*,
     record::{CompactRecorder, FullPrecisionSettings, NamedMpkFileRecorder, Recorder},
-    tensor::activation::{sigmoid, softmax},
 };
+use clap::Parser;
 use colored::Colorize;
 use model::Model;
-use serde::Deserialize;
-use sha2::{Digest, Sha256};
 use training::TrainingConfig;
 
-#[allow(unused_imports)]
-use crate::sites::{crypto_games::CryptoGames, duck_dice::DuckDiceIo, free_bitco_in::FreeBitcoIn};
-use crate::sites::{BetError, BetResult, Site, SiteCurrency};
-use crate::{config::SiteConfig, currency::Currency};
-use crate::{
-    config::{CryptoGamesConfig, DuckDiceConfig, FreeBitcoInConfig, TomlConfig, TomlStrategies},
-    model::ModelConfig,
+use crate::cli::{
+    Cli, Command, CommonArgs, CredentialsAction, CredentialsArgs, StateArgs, VerifyArgs,
 };
+use crate::config::TomlConfig;
+use crate::credentials::{Credentials, CredentialsError, Secret, PASSPHRASE_VAR};
+use crate::model::ModelConfig;
+use crate::reload::ConfigWatcher;
+use crate::runner::{print_summary, shutdown_signal, Supervisor};
+use crate::sites::audit;
+use crate::sites::provably_fair::roll_algorithm;
+use crate::sites::seed_chain::SeedChain;
+
+type MyBackend = Vulkan<f32, i32>;
+
+fn read_config(args: &CommonArgs) -> Result<TomlConfig, Box<dyn std::error::Error>> {
+    let config_contents = std::fs::read_to_string(&args.config)
+        .map_err(|err| format!("unable to read {}: {err}", args.config.display()))?;
+    let mut config: TomlConfig = toml::from_str(&config_contents)?;
+
+    if let Some(site) = args.site.filter(|_| config.sessions.is_empty()) {
+        config.set_active_site(site);
+    }
+    if let Some(strategy) = args.strategy {
+        config.set_strategy(strategy);
+    }
+    config.resolve_credentials(&Credentials::load(&args.credentials)?);
 
-struct Game<B: Backend> {
-    confidence: f32,
-    site: Box<dyn Site>,
-    model: Model<B>,
-    device: B::Device,
-    prediction: f32,
-    initialized: bool,
+    Ok(config)
 }
 
-impl<B: Backend> Game<B> {
-    async fn bet(&mut self) -> Result<(), BetError> {
-        if !self.initialized {
-            B::seed(42);
-            self.initialized = true;
-        }
-        let bet_result = match self.site.do_bet(self.prediction, self.confidence).await {
-            Ok(res) => res,
-            Err(err) => match err {
-                BetError::EmptyReply => return Ok(()),
-                _ => return Err(err),
-            },
-        };
+fn manage_credentials(args: CredentialsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let passphrase =
+        std::env::var(PASSPHRASE_VAR).map_err(|_| CredentialsError::MissingPassphrase)?;
+    let mut credentials = if args.credentials.exists() {
+        Credentials::open(&args.credentials, &passphrase)?
+    } else {
+        Credentials::default()
+    };
 
-        if bet_result.result {
-            self.site.on_win(&bet_result);
-            self.print_res(&bet_result, true);
-        } else {
-            self.site.on_lose(&bet_result);
-            self.print_res(&bet_result, false);
+    match args.action {
+        CredentialsAction::Set { name } => {
+            let mut value = String::new();
+            std::io::stdin().read_line(&mut value)?;
+            credentials.set(name, Secret::new(value.trim_end().to_string()));
+            credentials.save(&args.credentials, &passphrase)?;
         }
-
-        let history = self.site.get_history();
-        let history_size = self.site.get_history_size();
-        // Get server seed hash next roll and convert it to a tensor of shape (-1, 256).
-        if history.len() >= history_size {
-            let inputs_hash = history
-                .iter()
-                .flat_map(|itm| {
-                    let mut vals = itm
-                        .hash_next_roll
-                        .chars()
-                        .flat_map(|chr| {
-                            let value = chr.to_digit(16).unwrap_or(0);
-                            (0..4)
-                                .rev()
-                                .map(move |i| ((value >> i) & 1).elem::<B::FloatElem>())
-                        })
-                        .collect::<Vec<B::FloatElem>>();
-
-                    vals.resize(256, 0f32.elem::<B::FloatElem>());
-
-                    vals.append(
-                        &mut itm
-                            .hash_previous_roll
-                            .chars()
-                            .flat_map(|chr| {
-                                let value = chr.to_digit(16).unwrap_or(0);
-                                (0..4)
-                                    .rev()
-                                    .map(move |i| ((value >> i) & 1).elem::<B::FloatElem>())
-                            })
-                            .collect::<Vec<B::FloatElem>>(),
-                    );
-
-                    vals.resize(512, 0f32.elem::<B::FloatElem>());
-
-                    vals.append(
-                        &mut itm
-                            .client_seed
-                            .chars()
-                            .flat_map(|chr| {
-                                let value = chr.to_digit(16).unwrap_or(0);
-                                (0..4)
-                                    .rev()
-                                    .map(move |i| ((value >> i) & 1).elem::<B::FloatElem>())
-                            })
-                            .collect::<Vec<B::FloatElem>>(),
-                    );
-
-                    vals.resize(768, 0f32.elem::<B::FloatElem>());
-
-                    vals.append(
-                        &mut (0..32)
-                            .map(|i| ((itm.nonce >> i) & 1).elem::<B::FloatElem>())
-                            .collect::<Vec<B::FloatElem>>(),
-                    );
-
-                    vals.resize(1024, 0f32.elem::<B::FloatElem>());
-
-                    vals
-                })
-                .collect::<Vec<B::FloatElem>>();
-
-            let hash_data = TensorData::new(
-                inputs_hash,
-                [history.len() / history_size, history_size, 4, 256],
-            );
-            let hash_data: Tensor<B, 4> =
-                Tensor::from(hash_data.convert::<B::FloatElem>()).to_device(&self.device);
-
-            let output = self.model.forward(data::BetBatch {
-                inputs: hash_data,
-                targets: Tensor::zeros(Shape::new([1, 1]), &self.device),
-            });
-            let predicted_output = output
-                .clone()
-                .argmax(1)
-                .into_data()
-                .to_vec::<i32>()
-                .unwrap();
-            let predicted_output = predicted_output[0];
-            let confidence = output.clone().into_data().to_vec::<f32>().unwrap()
-                [predicted_output as usize]
-                * 100.;
-            // let predicted = (predicted_output[0] + 1.) * 10000. / 2.;
-            // let predicted = (((predicted - 4500.) / (5500. - 4500.)) * (10000. - 0.)) + 0.;
-
-            self.confidence = confidence;
-            self.prediction = predicted_output as f32 * 100.;
+        CredentialsAction::Remove { name } => {
+            if !credentials.remove(&name) {
+                println!("{name} is not stored");
+            }
+            credentials.save(&args.credentials, &passphrase)?;
+        }
+        CredentialsAction::List => {
+            for name in credentials.names() {
+                println!("{name}");
+            }
         }
-
-        Ok(())
     }
 
-    fn print_res(&self, bet_result: &BetResult, win: bool) {
-        let profit_str = &format!("Profit: {:.8}", self.site.get_profit());
-        let profit_str = if self.site.get_profit() > 0. {
-            profit_str.green()
-        } else {
-            profit_str.red()
-        };
+    Ok(())
+}
 
-        let golden_roll = if bet_result.number > 9900 || bet_result.number < 100 {
-            (&format!("{: <5}", bet_result.number)).yellow()
-        } else {
-            format!("{: <5}", bet_result.number).normal()
+fn verify(args: VerifyArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(ledger_path) = args.ledger else {
+        let Some(site) = args.common.site else {
+            return Err("--site is required to recompute a roll".into());
         };
-
-        let output_str = &format!(
-            "#{: >6} || Balance: {:0>.8} || Roll: {: <5} || Multiplier: {: <6.2} || Wagered: {:.8} || Predicted: {: <5.0} || Confidence: {: <2.2} || {}",
-            self.site.get_rolls(),
-            self.site.get_balance(),
-            golden_roll,
-            self.site.get_current_multiplier(),
-            self.site.get_current_bet(),
-            self.prediction,
-            self.confidence,
-            profit_str,
-        );
-        let output_str = if win {
-            output_str.green()
-        } else {
-            output_str.red()
+        let Some(algorithm) = roll_algorithm(site) else {
+            return Err(format!("no roll algorithm for {site:?}").into());
+        };
+        let (Some(server_seed), Some(client_seed), Some(nonce)) =
+            (args.server_seed, args.client_seed, args.nonce)
+        else {
+            return Err("--server-seed, --client-seed and --nonce are required".into());
         };
+        println!(
+            "Server seed hash: {}",
+            algorithm.server_seed_hash(&server_seed)
+        );
+        println!(
+            "Roll: {}",
+            algorithm.roll(&server_seed, &client_seed, nonce)
+        );
 
-        println!("{output_str}");
-    }
-}
+        return Ok(());
+    };
 
-#[tokio::main]
-async fn main() -> Result<(), BetError> {
-    let config_contents = tokio::fs::read_to_string("config.toml")
-        .await
-        .expect("config.toml not found.");
-    let game_config: TomlConfig =
-        toml::from_str(&config_contents).expect("Unable to read config.toml");
-
-    let site = if game_config.duck_dice.enabled {
-        DuckDiceIo::default()
-            .with_api_key(game_config.duck_dice.api_key.clone())
-            .with_currency(game_config.duck_dice.currency.clone())
-            .with_strategy(game_config.duck_dice.strategy)
-    } else {
-        unimplemented!("TODO: Add more sites");
+    let ledger = state::load_ledger(&ledger_path)
+        .map_err(|err| format!("unable to read {}: {err}", ledger_path.display()))?;
+    let site = args.common.site.or(ledger.site()).ok_or_else(|| {
+        format!(
+            "{} doesn't record its site, pass --site",
+            ledger_path.display()
+        )
+    })?;
+    let mut seeds = match &args.seeds {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|err| format!("unable to read {}: {err}", path.display()))?,
+        None => String::new(),
     };
+    if let Some(server_seed) = args.server_seed {
+        seeds.push('\n');
+        seeds.push_str(&server_seed);
+    }
 
-    type MyBackend = Vulkan<f32, i32>;
+    // Seeds the session fetched on rotation first, the ones given here take precedence.
+    let mut revealed = ledger
+        .rotations()
+        .iter()
+        .flat_map(|rotation| rotation.revealed.iter().cloned())
+        .collect::<HashMap<String, String>>();
+    revealed.extend(audit::parse_revealed(site, &seeds));
+
+    let audit = audit::audit(site, ledger.bets(), &revealed, ledger.house_edges());
+    for bet in &audit.bets {
+        let line = bet.to_string();
+        if !bet.failures.is_empty() {
+            println!("{}", line.red());
+        } else if bet.unverified.is_some() {
+            println!("{}", line.yellow());
+        } else {
+            println!("{}", line.green());
+        }
+    }
+    println!("{audit}");
 
-    let device = WgpuDevice::default();
-    let artifact_dir = "/home/jvne/Projects/rust/random_guesser/experimental";
+    let chain = SeedChain::build(site, ledger.bets(), ledger.rotations());
+    for issue in &chain.issues {
+        println!("{}", issue.to_string().red());
+    }
+    println!("{chain}");
+
+    if !audit.is_clean() || !chain.is_clean() {
+        return Err(format!(
+            "{} bet(s) failed the audit, {} seed chain issue(s)",
+            audit.failed(),
+            chain.issues.len()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn load_model<B: Backend>(artifact_dir: &Path, device: &B::Device) -> (Model<B>, TrainingConfig) {
+    let artifact_dir = artifact_dir.display();
 
     let config = TrainingConfig::load(format!("{artifact_dir}/config.json"))
         .expect("Config should exist for the model; run train first.");
 
     let record = CompactRecorder::new()
-        .load(format!("{artifact_dir}/model").into(), &device)
+        .load(format!("{artifact_dir}/model").into(), device)
         .expect("Trained model should exist; run train first.");
 
-    let model = ModelConfig::new().init(&device).load_record(record);
+    (ModelConfig::new().init(device).load_record(record), config)
+}
 
-    let mut game = Game::<MyBackend> {
-        confidence: 0.,
-        site: Box::new(DuckDiceIo::default()),
-        model,
-        device,
-        prediction: 0.,
-        initialized: false,
-    };
-    game.site.login().await?;
+async fn play(
+    args: &CommonArgs,
+    state_args: &StateArgs,
+    rolls: Option<u64>,
+    paper: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let game_config = read_config(args)?;
+    let issues = validate::validate(&game_config);
+    if !issues.is_empty() {
+        for issue in &issues {
+            eprintln!("{}", issue.to_string().red());
+        }
+        return Err(format!(
+            "{} problem(s) in {}, run inspect-config for details",
+            issues.len(),
+            args.config.display()
+        )
+        .into());
+    }
+    let all_sessions = game_config.effective_sessions()?;
+    let mut watcher = ConfigWatcher::new(args.config.clone(), &all_sessions, paper);
+
+    let mut sessions = Vec::new();
+    for session in &all_sessions {
+        if args.site.is_some_and(|site| site != session.site) {
+            continue;
+        }
+
+        let site = sites::from_config(&session.config, paper || session.paper)?;
+        sessions.push((session.name.clone(), site, watcher.subscribe(session)));
+    }
+
+    let device = WgpuDevice::default();
+    let (model, training_config) = load_model::<MyBackend>(&args.artifact_dir, &device);
+
+    let reload_args = args.clone();
+    watcher.spawn(move || read_config(&reload_args).map_err(|err| err.to_string()));
+
+    let supervisor = sessions.into_iter().fold(
+        Supervisor::<MyBackend>::new(model, device, training_config.seed)
+            .with_state_dir(state_args.state_dir.clone(), state_args.resume),
+        |supervisor, (name, site, settings)| supervisor.with_session(name, site, settings),
+    );
+    let summaries = supervisor.run(rolls, shutdown_signal()).await;
+    print_summary(&summaries);
+
+    Ok(())
+}
 
-    loop {
-        game.bet().await?;
+#[tokio::main]
+async fn main() -> ExitCode {
+    if let Err(err) = run(Cli::parse()).await {
+        eprintln!("{}", format!("Error: {err}").red());
 
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        return ExitCode::FAILURE;
     }
+
+    ExitCode::SUCCESS
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    match cli.command {
+        Command::Play(args) => play(&args.common, &args.state, args.rolls, false).await?,
+        Command::Simulate(args) => play(&args.common, &args.state, Some(args.rolls), true).await?,
+        Command::Train(args) => {
+            let artifact_dir = args.artifact_dir.display().to_string();
+
+            training::train::<Autodiff<MyBackend>>(
+                &artifact_dir,
+                TrainingConfig::new(AdamConfig::new()),
+                WgpuDevice::default(),
+            );
+        }
+        Command::Verify(args) => verify(args)?,
+        Command::Export(args) => {
+            let device = WgpuDevice::default();
+            let (model, _) = load_model::<MyBackend>(&args.common.artifact_dir, &device);
+            let output = args
+                .output
+                .unwrap_or_else(|| args.common.artifact_dir.join("model_full"));
+
+            model
+                .save_file(
+                    output,
+                    &NamedMpkFileRecorder::<FullPrecisionSettings>::new(),
+                )
+                .expect("Model should be exported successfully");
+        }
+        Command::InspectConfig(args) => {
+            let game_config = read_config(&args)?;
+            let issues = validate::validate(&game_config);
+
+            for issue in &issues {
+                println!("{}", issue.to_string().red());
+            }
+            if let Ok(sessions) = game_config.effective_sessions() {
+                println!("{}", validate::effective_toml(&game_config, &sessions));
+            }
+            if !issues.is_empty() {
+                return Err(
+                    format!("{} problem(s) in {}", issues.len(), args.config.display()).into(),
+                );
+            }
+        }
+        Command::Credentials(args) => manage_credentials(args)?,
+    }
+
+    Ok(())
 }
@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use colored::Colorize;
+use tokio::sync::watch;
+
+use crate::config::{EffectiveSession, LimitsConfig, ReconcileConfig, TomlConfig, TomlStrategies};
+use crate::currency::Currency;
+use crate::sites;
+use crate::validate;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The part of a session's config that can change while it runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiveSettings {
+    pub bet_delay: Duration,
+    pub limits: LimitsConfig,
+    pub reconcile: ReconcileConfig,
+    pub fallback_currencies: Vec<Currency>,
+    pub strategy_params: toml::Table,
+}
+
+impl LiveSettings {
+    pub fn from_config(config: &TomlConfig) -> Self {
+        let strategy_params = config
+            .active_site()
+            .map(|site| config.strategy(site).1.clone())
+            .unwrap_or_default();
+
+        Self {
+            bet_delay: Duration::from_millis(config.runner.bet_delay_ms),
+            limits: config.runner.limits.clone(),
+            reconcile: config.runner.reconcile.clone(),
+            fallback_currencies: config.runner.fallback_currencies.clone(),
+            strategy_params,
+        }
+    }
+
+    /// One line per changed setting, e.g. `bet_delay_ms: 100 -> 250`.
+    fn diff(&self, new: &Self) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        if self.bet_delay != new.bet_delay {
+            changes.push(format!(
+                "bet_delay_ms: {} -> {}",
+                self.bet_delay.as_millis(),
+                new.bet_delay.as_millis()
+            ));
+        }
+        push_change(
+            &mut changes,
+            "limits.stop_loss",
+            self.limits.stop_loss,
+            new.limits.stop_loss,
+        );
+        push_change(
+            &mut changes,
+            "limits.take_profit",
+            self.limits.take_profit,
+            new.limits.take_profit,
+        );
+        push_change(
+            &mut changes,
+            "limits.max_loss_streak",
+            self.limits.max_loss_streak,
+            new.limits.max_loss_streak,
+        );
+        push_change(
+            &mut changes,
+            "reconcile.every",
+            self.reconcile.every,
+            new.reconcile.every,
+        );
+        push_change(
+            &mut changes,
+            "reconcile.max_drift",
+            self.reconcile.max_drift,
+            new.reconcile.max_drift,
+        );
+        push_change(
+            &mut changes,
+            "reconcile.pause_on_drift",
+            Some(self.reconcile.pause_on_drift),
+            Some(new.reconcile.pause_on_drift),
+        );
+        if self.fallback_currencies != new.fallback_currencies {
+            let show = |currencies: &[Currency]| {
+                currencies
+                    .iter()
+                    .map(Currency::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            };
+            changes.push(format!(
+                "fallback_currencies: [{}] -> [{}]",
+                show(&self.fallback_currencies),
+                show(&new.fallback_currencies)
+            ));
+        }
+
+        let mut keys = self
+            .strategy_params
+            .keys()
+            .chain(new.strategy_params.keys())
+            .collect::<Vec<&String>>();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            push_change(
+                &mut changes,
+                &format!("strategy_params.{key}"),
+                self.strategy_params.get(key),
+                new.strategy_params.get(key),
+            );
+        }
+
+        changes
+    }
+}
+
+fn push_change<T: PartialEq + std::fmt::Display>(
+    changes: &mut Vec<String>,
+    name: &str,
+    old: Option<T>,
+    new: Option<T>,
+) {
+    if old == new {
+        return;
+    }
+
+    let show = |value: Option<T>| value.map_or("<unset>".to_string(), |value| value.to_string());
+    changes.push(format!("{name}: {} -> {}", show(old), show(new)));
+}
+
+fn strategy(session: &EffectiveSession) -> Option<TomlStrategies> {
+    let site = session.config.active_site().ok()?;
+
+    Some(session.config.strategy(site).0)
+}
+
+/// Settings that only take effect when the session is rebuilt.
+fn restart_required(old: &EffectiveSession, new: &EffectiveSession) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    let (old_site, new_site) = (old.config.active_site().ok(), new.config.active_site().ok());
+    if old_site != new_site {
+        changes.push(format!("site: {old_site:?} -> {new_site:?}"));
+    }
+    if old.config.currency() != new.config.currency() {
+        changes.push(format!(
+            "currency: {:?} -> {:?}",
+            old.config.currency(),
+            new.config.currency()
+        ));
+    }
+    if strategy(old) != strategy(new) {
+        changes.push(format!(
+            "strategy: {:?} -> {:?}",
+            strategy(old),
+            strategy(new)
+        ));
+    }
+    if old.paper != new.paper {
+        changes.push(format!("paper: {} -> {}", old.paper, new.paper));
+    }
+
+    let credentials = |config: &TomlConfig| {
+        [
+            config.crypto_games.api_key.expose().to_string(),
+            config.freebitcoin.btc_address.clone(),
+            config.freebitcoin.password.expose().to_string(),
+            config.duck_dice.api_key.expose().to_string(),
+            config.windice.api_key.expose().to_string(),
+        ]
+    };
+    if credentials(&old.config) != credentials(&new.config) {
+        changes.push("credentials".to_string());
+    }
+
+    changes
+}
+
+struct RunningSession {
+    started: EffectiveSession,
+    applied: LiveSettings,
+    sender: watch::Sender<LiveSettings>,
+}
+
+/// Watches config.toml and pushes the live settings of every running session through its
+/// channel, changes that need a restart are logged and ignored.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    paper: bool,
+    known: Vec<String>,
+    running: HashMap<String, RunningSession>,
+}
+
+impl ConfigWatcher {
+    /// `sessions` is the full session list, `paper` the override passed to `from_config`.
+    pub fn new(path: PathBuf, sessions: &[EffectiveSession], paper: bool) -> Self {
+        Self {
+            path,
+            paper,
+            known: sessions
+                .iter()
+                .map(|session| session.name.clone())
+                .collect(),
+            running: HashMap::new(),
+        }
+    }
+
+    /// Registers a session that is actually run and returns its settings channel.
+    pub fn subscribe(&mut self, session: &EffectiveSession) -> watch::Receiver<LiveSettings> {
+        let applied = LiveSettings::from_config(&session.config);
+        let (sender, receiver) = watch::channel(applied.clone());
+        self.running.insert(
+            session.name.clone(),
+            RunningSession {
+                started: session.clone(),
+                applied,
+                sender,
+            },
+        );
+
+        receiver
+    }
+
+    /// Polls the config's modification time and reloads it with `load` whenever it changes.
+    pub fn spawn<F>(mut self, load: F)
+    where
+        F: Fn() -> Result<TomlConfig, String> + Send + Sync + 'static,
+    {
+        let load = std::sync::Arc::new(load);
+
+        tokio::spawn(async move {
+            let mut last_modified = modified(&self.path);
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+            loop {
+                interval.tick().await;
+                if self
+                    .running
+                    .values()
+                    .all(|session| session.sender.is_closed())
+                {
+                    break;
+                }
+
+                let current = modified(&self.path);
+                if current == last_modified {
+                    continue;
+                }
+                last_modified = current;
+
+                let load = std::sync::Arc::clone(&load);
+                match tokio::task::spawn_blocking(move || load()).await {
+                    Ok(Ok(config)) => self.reload(&config),
+                    Ok(Err(err)) => eprintln!(
+                        "{}",
+                        format!("Config reload rejected, keeping the running config: {err}").red()
+                    ),
+                    Err(err) => eprintln!("{}", format!("Config reload panicked: {err}").red()),
+                }
+            }
+        });
+    }
+
+    fn reload(&mut self, config: &TomlConfig) {
+        let issues = validate::validate(config);
+        if !issues.is_empty() {
+            eprintln!(
+                "{}",
+                "Config reload rejected, keeping the running config:".red()
+            );
+            for issue in issues {
+                eprintln!("{}", issue.to_string().red());
+            }
+            return;
+        }
+        let sessions = match config.effective_sessions() {
+            Ok(sessions) => sessions,
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    format!("Config reload rejected, keeping the running config: {err}").red()
+                );
+                return;
+            }
+        };
+        // Build every site without logging in so bad params or currencies are caught up front.
+        for session in &sessions {
+            if let Err(err) = sites::from_config(&session.config, self.paper || session.paper) {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Config reload rejected, keeping the running config: [{}] {err}",
+                        session.name
+                    )
+                    .red()
+                );
+                return;
+            }
+        }
+
+        for name in &self.known {
+            if !sessions.iter().any(|session| &session.name == name) {
+                println!(
+                    "{}",
+                    format!("[{name}] Removed from the config, a restart is needed to stop it")
+                        .yellow()
+                );
+            }
+        }
+
+        for session in sessions {
+            if !self.known.contains(&session.name) {
+                println!(
+                    "{}",
+                    format!(
+                        "[{}] Added to the config, a restart is needed to start it",
+                        session.name
+                    )
+                    .yellow()
+                );
+                continue;
+            }
+            let Some(running) = self.running.get_mut(&session.name) else {
+                continue;
+            };
+
+            for change in restart_required(&running.started, &session) {
+                println!(
+                    "{}",
+                    format!("[{}] Ignoring {change}, it needs a restart", session.name).yellow()
+                );
+            }
+
+            let mut settings = LiveSettings::from_config(&session.config);
+            // Params written for a different strategy don't apply to the running one.
+            if strategy(&running.started) != strategy(&session) {
+                settings.strategy_params = running.applied.strategy_params.clone();
+            }
+            let changes = running.applied.diff(&settings);
+            if changes.is_empty() {
+                continue;
+            }
+            for change in changes {
+                println!("[{}] Reloaded {change}", session.name);
+            }
+            running.applied = settings.clone();
+            let _ = running.sender.send(settings);
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TomlConfig {
+        toml::from_str(
+            r#"
+            [crypto_games]
+            enabled = false
+            api_key = "key"
+            currency = "BTC"
+            strategy = "None"
+
+            [freebitcoin]
+            enabled = false
+            btc_address = "address"
+            password = "password"
+            strategy = "None"
+
+            [duck_dice]
+            enabled = true
+            api_key = "key"
+            currency = "BTC"
+            strategy = "None"
+            strategy_params = { min_chance = 1.0 }
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn session(config: &TomlConfig) -> EffectiveSession {
+        config.effective_sessions().unwrap().remove(0)
+    }
+
+    #[test]
+    fn diff_lists_each_changed_setting() {
+        let old = config();
+        let mut new = config();
+        new.runner.bet_delay_ms = 250;
+        new.duck_dice
+            .strategy_params
+            .insert("max_chance".to_string(), toml::Value::Float(40.));
+        new.duck_dice.strategy_params.remove("min_chance");
+
+        let changes = LiveSettings::from_config(&old).diff(&LiveSettings::from_config(&new));
+        assert_eq!(
+            changes,
+            [
+                "bet_delay_ms: 100 -> 250",
+                "strategy_params.max_chance: <unset> -> 40.0",
+                "strategy_params.min_chance: 1.0 -> <unset>",
+            ]
+        );
+        assert!(LiveSettings::from_config(&old)
+            .diff(&LiveSettings::from_config(&old))
+            .is_empty());
+    }
+
+    #[test]
+    fn site_currency_strategy_and_credentials_need_a_restart() {
+        let old = config();
+        let mut new = config();
+        new.runner.bet_delay_ms = 250;
+        assert!(restart_required(&session(&old), &session(&new)).is_empty());
+
+        new.duck_dice.currency = Currency::ETH;
+        new.duck_dice.strategy = TomlStrategies::AiFight;
+        new.duck_dice.api_key = crate::credentials::Secret::new("other".to_string());
+        assert_eq!(
+            restart_required(&session(&old), &session(&new)),
+            [
+                "currency: Some(BTC) -> Some(ETH)",
+                "strategy: Some(None) -> Some(AiFight)",
+                "credentials",
+            ]
+        );
+    }
+
+    #[test]
+    fn reload_only_sends_valid_live_changes() {
+        let old = config();
+        let mut watcher = ConfigWatcher::new(PathBuf::new(), &[session(&old)], false);
+        let mut receiver = watcher.subscribe(&session(&old));
+
+        let mut invalid = config();
+        invalid.runner.limits.max_loss_streak = Some(0);
+        invalid.runner.bet_delay_ms = 250;
+        watcher.reload(&invalid);
+        assert!(!receiver.has_changed().unwrap());
+
+        let mut restart_only = config();
+        restart_only.duck_dice.currency = Currency::ETH;
+        watcher.reload(&restart_only);
+        assert!(!receiver.has_changed().unwrap());
+
+        let mut params = config();
+        params
+            .duck_dice
+            .strategy_params
+            .insert("min_chance".to_string(), toml::Value::Float(2.));
+        watcher.reload(&params);
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(
+            receiver.borrow_and_update().strategy_params["min_chance"],
+            toml::Value::Float(2.)
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use burn::prelude::*;
+use colored::Colorize;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+use crate::game::{Game, SessionStats};
+use crate::model::Model;
+use crate::reload::LiveSettings;
+use crate::sites::Site;
+
+#[derive(Debug)]
+pub struct SessionSummary {
+    pub name: String,
+    pub stats: SessionStats,
+    pub profit: f32,
+    pub balance: f32,
+    /// Profit per currency, its current one first.
+    pub profits: Vec<(String, f32)>,
+    pub error: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+/// Runs several `Game`s at once, all sharing one loaded model and device.
+///
+/// `Model` is not `Sync`, so sessions take turns on it for the forward pass only.
+pub struct Supervisor<B: Backend> {
+    model: Arc<Mutex<Model<B>>>,
+    device: B::Device,
+    seed: u64,
+    sessions: Vec<(String, Box<dyn Site>, watch::Receiver<LiveSettings>)>,
+    state_dir: Option<PathBuf>,
+    resume: bool,
+}
+
+impl<B> Supervisor<B>
+where
+    B: Backend,
+    Model<B>: Send,
+{
+    pub fn new(model: Model<B>, device: B::Device, seed: u64) -> Self {
+        Self {
+            model: Arc::new(Mutex::new(model)),
+            device,
+            seed,
+            sessions: Vec::new(),
+            state_dir: None,
+            resume: false,
+        }
+    }
+
+    pub fn with_state_dir(mut self, state_dir: PathBuf, resume: bool) -> Self {
+        self.state_dir = Some(state_dir);
+        self.resume = resume;
+
+        self
+    }
+
+    /// Adds a session, `settings` carries its reloadable settings, see `reload::ConfigWatcher`.
+    pub fn with_session(
+        mut self,
+        name: String,
+        site: Box<dyn Site>,
+        settings: watch::Receiver<LiveSettings>,
+    ) -> Self {
+        self.sessions.push((name, site, settings));
+
+        self
+    }
+
+    /// Runs every session until it hits `rolls` or fails, one failing session does not stop
+    /// the others.
+    pub async fn run(
+        self,
+        rolls: Option<u64>,
+        shutdown: watch::Receiver<bool>,
+    ) -> Vec<SessionSummary> {
+        let mut tasks = JoinSet::new();
+
+        for (name, site, settings) in self.sessions {
+            let mut game = Game::new(
+                name,
+                site,
+                Arc::clone(&self.model),
+                self.device.clone(),
+                self.seed,
+                settings,
+            );
+            if let Some(state_dir) = &self.state_dir {
+                game = game.with_state_dir(state_dir.clone(), self.resume);
+            }
+            let shutdown = shutdown.clone();
+
+            tasks.spawn(async move {
+                let error = game.run(rolls, shutdown).await.err();
+
+                SessionSummary {
+                    name: game.name.clone(),
+                    stats: game.stats.clone(),
+                    profit: game.site().get_profit(),
+                    balance: game.site().get_balance(),
+                    profits: game.site().get_currency_profits(),
+                    error,
+                }
+            });
+        }
+
+        let mut summaries = Vec::new();
+        while let Some(summary) = tasks.join_next().await {
+            match summary {
+                Ok(summary) => summaries.push(summary),
+                Err(err) => eprintln!("{}", format!("Session task panicked: {err}").red()),
+            }
+        }
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        summaries
+    }
+}
+
+pub fn print_summary(summaries: &[SessionSummary]) {
+    println!(
+        "{: <20} || {: >8} || {: >8} || {: >8} || {: >14} || {: >14} || {: >14}",
+        "Session", "Rolls", "Wins", "Losses", "Wagered", "Balance", "Profit"
+    );
+
+    let mut total = SessionStats::default();
+    let mut total_profit = 0.;
+    for summary in summaries {
+        println!(
+            "{: <20} || {: >8} || {: >8} || {: >8} || {: >14.8} || {: >14.8} || {: >14.8}",
+            summary.name,
+            summary.stats.rolls,
+            summary.stats.wins,
+            summary.stats.losses,
+            summary.stats.wagered,
+            summary.balance,
+            summary.profit,
+        );
+        if summary.profits.len() > 1 {
+            for (symbol, profit) in &summary.profits {
+                println!("    {symbol: <8} profit {profit:.8}");
+            }
+        }
+        if let Some(err) = &summary.error {
+            println!("{}", format!("    stopped: {err}").red());
+        }
+
+        total.rolls += summary.stats.rolls;
+        total.wins += summary.stats.wins;
+        total.losses += summary.stats.losses;
+        total.wagered += summary.stats.wagered;
+        total_profit += summary.profit;
+    }
+
+    let total_str = format!(
+        "{: <20} || {: >8} || {: >8} || {: >8} || {: >14.8} || {: >14} || {: >14.8}",
+        "Total", total.rolls, total.wins, total.losses, total.wagered, "", total_profit,
+    );
+    if total_profit > 0. {
+        println!("{}", total_str.green());
+    } else {
+        println!("{}", total_str.red());
+    }
+}
+
+/// Flips to `true` on Ctrl-C or SIGTERM.
+pub fn shutdown_signal() -> watch::Receiver<bool> {
+    let (sender, receiver) = watch::channel(false);
+
+    tokio::spawn(async move {
+        #[cfg(unix)]
+        {
+            let mut terminate =
+                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                    .expect("Failed to listen for SIGTERM");
+
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = tokio::signal::ctrl_c().await;
+
+        println!("{}", "Shutting down after the current bets...".yellow());
+        let _ = sender.send(true);
+    });
+
+    receiver
+}
@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sites::ledger::bet_key;
+use crate::sites::provably_fair::roll_algorithm;
+use crate::sites::{BetKind, BetOutcome, SeedInfo, Sites};
+
+/// Something a recorded bet doesn't square with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuditFailure {
+    /// The revealed server seed doesn't hash to the commitment the bet was placed under.
+    Commitment {
+        committed: String,
+        server_seed_hash: String,
+    },
+    Roll {
+        recorded: u32,
+        recomputed: u32,
+    },
+    /// The result doesn't follow from the roll.
+    Outcome {
+        number: u32,
+        kind: BetKind,
+        chance: f32,
+        result: bool,
+    },
+    /// The payout strays from the site's advertised house edge.
+    Payout {
+        recorded: f32,
+        advertised: f32,
+    },
+}
+
+impl fmt::Display for AuditFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Commitment {
+                committed,
+                server_seed_hash,
+            } => write!(
+                f,
+                "server seed hashes to {server_seed_hash}, not the committed {committed}"
+            ),
+            Self::Roll {
+                recorded,
+                recomputed,
+            } => write!(f, "roll {recorded} recomputes to {recomputed}"),
+            Self::Outcome {
+                number,
+                kind,
+                chance,
+                result,
+            } => write!(
+                f,
+                "roll {number} on {kind} at {chance}% recorded as a {}",
+                if *result { "win" } else { "loss" }
+            ),
+            Self::Payout {
+                recorded,
+                advertised,
+            } => write!(
+                f,
+                "payout {recorded:.4}, the house edge gives {advertised:.4}"
+            ),
+        }
+    }
+}
+
+/// What the audit found about one bet.
+#[derive(Clone, Debug)]
+pub struct BetAudit {
+    /// The site's bet id, or the roll on sites without one.
+    pub bet: String,
+    pub failures: Vec<AuditFailure>,
+    /// Why the roll couldn't be recomputed, e.g. its server seed isn't revealed yet.
+    pub unverified: Option<String>,
+}
+
+impl BetAudit {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty() && self.unverified.is_none()
+    }
+}
+
+impl fmt::Display for BetAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.failures.is_empty() {
+            let failures = self
+                .failures
+                .iter()
+                .map(AuditFailure::to_string)
+                .collect::<Vec<String>>();
+            write!(f, "FAIL {}: {}", self.bet, failures.join("; "))
+        } else if let Some(reason) = &self.unverified {
+            write!(f, "SKIP {}: {reason}", self.bet)
+        } else {
+            write!(f, "PASS {}", self.bet)
+        }
+    }
+}
+
+/// Per-bet results of auditing a ledger, in ledger order.
+#[derive(Clone, Debug, Default)]
+pub struct Audit {
+    pub bets: Vec<BetAudit>,
+}
+
+impl Audit {
+    pub fn passed(&self) -> usize {
+        self.bets.iter().filter(|bet| bet.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.bets
+            .iter()
+            .filter(|bet| !bet.failures.is_empty())
+            .count()
+    }
+
+    /// Bets with nothing wrong whose roll couldn't be recomputed.
+    pub fn unverified(&self) -> usize {
+        self.bets.len() - self.passed() - self.failed()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.failed() == 0
+    }
+}
+
+impl fmt::Display for Audit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} bets: {} passed, {} failed, {} unverified",
+            self.bets.len(),
+            self.passed(),
+            self.failed(),
+            self.unverified()
+        )
+    }
+}
+
+/// The audit of a retired seed pair's bets as the ledger keeps it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PairAudit {
+    pub server_seed_hash: String,
+    pub client_seed: String,
+    pub bets: usize,
+    pub passed: usize,
+    pub unverified: usize,
+    /// A `BetAudit` line per failed bet.
+    pub failures: Vec<String>,
+    /// Why the audit didn't run to the end, leaving every bet unverified.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl PairAudit {
+    pub fn new(retired: &SeedInfo, audit: &Audit) -> Self {
+        Self {
+            server_seed_hash: retired.server_seed_hash.clone(),
+            client_seed: retired.client_seed.clone(),
+            bets: audit.bets.len(),
+            passed: audit.passed(),
+            unverified: audit.unverified(),
+            failures: audit
+                .bets
+                .iter()
+                .filter(|bet| !bet.failures.is_empty())
+                .map(BetAudit::to_string)
+                .collect(),
+            error: None,
+        }
+    }
+
+    /// The audit of `bets` bets that never finished.
+    pub fn aborted(retired: &SeedInfo, bets: usize, error: String) -> Self {
+        Self {
+            server_seed_hash: retired.server_seed_hash.clone(),
+            client_seed: retired.client_seed.clone(),
+            bets,
+            passed: 0,
+            unverified: bets,
+            failures: Vec::new(),
+            error: Some(error),
+        }
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for PairAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pair {}/{}: {} bets, {} passed, {} failed, {} unverified",
+            self.server_seed_hash,
+            self.client_seed,
+            self.bets,
+            self.passed,
+            self.failures.len(),
+            self.unverified
+        )?;
+        if let Some(error) = &self.error {
+            write!(f, ", audit aborted: {error}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Revealed server seeds keyed by the hash they were committed to. Each line holds
+/// `<server seed hash> <server seed>`, or just the seed to have its hash computed with `site`'s
+/// algorithm. Blank lines and `#` comments are skipped, as are bare seeds on sites without one.
+pub fn parse_revealed(site: Sites, text: &str) -> HashMap<String, String> {
+    let algorithm = roll_algorithm(site);
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match line.split_once(char::is_whitespace) {
+            Some((hash, server_seed)) => Some((hash.to_string(), server_seed.trim().to_string())),
+            None => algorithm.map(|algorithm| (algorithm.server_seed_hash(line), line.to_string())),
+        })
+        .collect()
+}
+
+/// The commitment `site` recorded for the seed `bets[index]` was rolled from, and the seed
+/// itself on sites that reveal it with the bet.
+pub fn commitment(site: Sites, bets: &[BetOutcome], index: usize) -> (Option<&str>, Option<&str>) {
+    let bet = &bets[index];
+    let (committed, server_seed) = match site {
+        // The reply carries this roll's seed and the next one's hash.
+        Sites::CryptoGames => (
+            index
+                .checked_sub(1)
+                .map(|previous| bets[previous].hash_next_roll.as_str()),
+            Some(bet.hash_previous_roll.as_str()),
+        ),
+        // The reply describes the roll just made as the previous one.
+        Sites::FreeBitcoIn => (Some(bet.hash_previous_roll.as_str()), None),
+        Sites::DuckDiceIo | Sites::Windice => (Some(bet.hash_next_roll.as_str()), None),
+    };
+
+    (
+        committed.filter(|hash| !hash.is_empty()),
+        server_seed.filter(|seed| !seed.is_empty()),
+    )
+}
+
+/// The commitments whose server seeds `audit` needs revealed to recompute `bets`.
+pub fn commitments(site: Sites, bets: &[BetOutcome]) -> Vec<String> {
+    let mut commitments = Vec::new();
+    for index in 0..bets.len() {
+        if let (Some(committed), None) = commitment(site, bets, index) {
+            if !commitments.iter().any(|hash| hash == committed) {
+                commitments.push(committed.to_string());
+            }
+        }
+    }
+
+    commitments
+}
+
+/// Checks every bet `site` recorded against its seeds, roll, result and payout. `revealed` maps
+/// server seed hashes to their seeds, see `parse_revealed`. Payouts are only checked in currencies
+/// `house_edges` holds the advertised edge of.
+pub fn audit(
+    site: Sites,
+    bets: &[BetOutcome],
+    revealed: &HashMap<String, String>,
+    house_edges: &HashMap<String, f32>,
+) -> Audit {
+    let algorithm = roll_algorithm(site);
+
+    let bets = bets
+        .iter()
+        .enumerate()
+        .map(|(index, bet)| {
+            let mut failures = Vec::new();
+
+            let (committed, carried) = commitment(site, bets, index);
+            let server_seed = carried.or_else(|| {
+                committed
+                    .and_then(|hash| revealed.get(hash))
+                    .map(String::as_str)
+            });
+            let unverified = match (committed, server_seed, algorithm) {
+                (_, _, None) => Some(format!("no roll algorithm for {site:?}")),
+                _ if bet.client_seed.is_empty() => Some("no client seed recorded".to_string()),
+                (None, _, _) => Some("no server seed commitment recorded".to_string()),
+                (Some(committed), None, _) => {
+                    Some(format!("server seed of {committed} not revealed"))
+                }
+                (Some(committed), Some(server_seed), Some(algorithm)) => {
+                    let server_seed_hash = algorithm.server_seed_hash(server_seed);
+                    if server_seed_hash != committed {
+                        failures.push(AuditFailure::Commitment {
+                            committed: committed.to_string(),
+                            server_seed_hash,
+                        });
+                    }
+                    let recomputed =
+                        algorithm.roll(server_seed, &bet.client_seed, bet.nonce as u64);
+                    if recomputed != bet.number {
+                        failures.push(AuditFailure::Roll {
+                            recorded: bet.number,
+                            recomputed,
+                        });
+                    }
+
+                    None
+                }
+            };
+
+            if bet.kind.wins(bet.number, bet.chance, site.max_roll()) != bet.result {
+                failures.push(AuditFailure::Outcome {
+                    number: bet.number,
+                    kind: bet.kind,
+                    chance: bet.chance,
+                    result: bet.result,
+                });
+            }
+            // Sites show payouts to a few decimals.
+            if let Some(house_edge) = house_edges.get(&bet.symbol) {
+                let advertised =
+                    (100. - house_edge) / bet.kind.range_chance().unwrap_or(bet.chance);
+                if (bet.payout - advertised).abs() > 1e-4 + 1e-3 * advertised {
+                    failures.push(AuditFailure::Payout {
+                        recorded: bet.payout,
+                        advertised,
+                    });
+                }
+            }
+
+            BetAudit {
+                bet: bet_key(bet),
+                failures,
+                unverified,
+            }
+        })
+        .collect();
+
+    Audit { bets }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sites::provably_fair::{DuckDiceRoll, RollAlgorithm};
+    use crate::sites::MAX_ROLL;
+
+    const SERVER_SEED: &str = "a3f1c9e07b5d42e8";
+
+    fn bet(nonce: u32) -> BetOutcome {
+        let number = DuckDiceRoll.roll(SERVER_SEED, "client", nonce as u64);
+
+        BetOutcome {
+            bet_id: format!("bet-{nonce}"),
+            hash_previous_roll: String::new(),
+            hash_next_roll: DuckDiceRoll.server_seed_hash(SERVER_SEED),
+            client_seed: "client".to_string(),
+            nonce,
+            symbol: "BTC".to_string(),
+            result: BetKind::Over.wins(number, 49.5, MAX_ROLL),
+            is_high: true,
+            kind: BetKind::Over,
+            number,
+            threshold: BetKind::Over.threshold(49.5, MAX_ROLL),
+            chance: 49.5,
+            payout: 2.,
+            bet_amount: 0.001,
+            win_amount: 0.,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn tampered_bets_fail() {
+        let mut bets = (0..5).map(bet).collect::<Vec<BetOutcome>>();
+        bets[1].number = (bets[1].number + 1) % 10000;
+        bets[1].result = BetKind::Over.wins(bets[1].number, 49.5, MAX_ROLL);
+        bets[2].result = !bets[2].result;
+        bets[3].payout = 2.1;
+        bets[4].hash_next_roll = "elsewhere".to_string();
+
+        let revealed = parse_revealed(Sites::DuckDiceIo, &format!("# revealed\n{SERVER_SEED}\n"));
+        let house_edges = HashMap::from([("BTC".to_string(), 1.)]);
+        let report = audit(Sites::DuckDiceIo, &bets, &revealed, &house_edges);
+        assert!(report.bets[0].passed(), "{}", report.bets[0]);
+        assert!(matches!(
+            report.bets[1].failures[..],
+            [AuditFailure::Roll { .. }]
+        ));
+        assert!(matches!(
+            report.bets[2].failures[..],
+            [AuditFailure::Outcome { .. }]
+        ));
+        assert!(matches!(
+            report.bets[3].failures[..],
+            [AuditFailure::Payout { .. }]
+        ));
+        assert!(report.bets[4].failures.is_empty() && report.bets[4].unverified.is_some());
+        assert_eq!(
+            (report.passed(), report.failed(), report.unverified()),
+            (1, 3, 1)
+        );
+
+        // freebitco.in rolls up to 10000, which wins every over bet.
+        let mut edge = bet(0);
+        (edge.number, edge.chance, edge.result) = (10000, 47.5, true);
+        let report = audit(Sites::FreeBitcoIn, &[edge], &revealed, &HashMap::new());
+        assert!(report.bets[0].failures.is_empty(), "{}", report.bets[0]);
+
+        let revealed = parse_revealed(Sites::DuckDiceIo, "elsewhere not-the-seed");
+        let report = audit(Sites::DuckDiceIo, &bets[4..], &revealed, &house_edges);
+        assert!(matches!(
+            report.bets[0].failures[..],
+            [AuditFailure::Commitment { .. }, AuditFailure::Roll { .. }]
+        ));
+
+        // No roll algorithm and no house edge, only the outcome is left to check.
+        let report = audit(Sites::Windice, &bets, &revealed, &HashMap::new());
+        assert_eq!(report.failed(), 1, "{report}");
+        assert!(report.bets[0].unverified.is_some());
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sites::{BetError, Sites};
+
+/// Rolls run from 0 to `MAX_ROLL` on every site but freebitco.in, see `Sites::max_roll`. Ranges
+/// are only taken by Windice so they stay within it.
+pub const MAX_ROLL: u32 = 9999;
+
+/// How many of the `MAX_ROLL + 1` rolls `chance` percent covers, rounded so float error in the
+/// chance doesn't cost a roll.
+pub fn chance_rolls(chance: f32) -> u32 {
+    (chance * 100.).round() as u32
+}
+
+/// What a bet wins on, ranges are inclusive on both ends.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BetKind {
+    /// Wins above the site's max roll minus `chance * 100`.
+    #[default]
+    Over,
+    /// Wins below `chance * 100`.
+    Under,
+    InRange(u32, u32),
+    OutOfRange(u32, u32),
+}
+
+impl fmt::Display for BetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Over => write!(f, "over"),
+            Self::Under => write!(f, "under"),
+            Self::InRange(lo, hi) => write!(f, "in {lo}..={hi}"),
+            Self::OutOfRange(lo, hi) => write!(f, "out of {lo}..={hi}"),
+        }
+    }
+}
+
+impl BetKind {
+    pub fn from_high(high: bool) -> Self {
+        if high {
+            Self::Over
+        } else {
+            Self::Under
+        }
+    }
+
+    /// The `width` rolls wide range holding `prediction`, the model predicts buckets of rolls.
+    pub fn bucket(prediction: f32, width: u32) -> Self {
+        let width = width.clamp(1, MAX_ROLL + 1);
+        let lo = (prediction.max(0.) as u32).min(MAX_ROLL) / width * width;
+
+        Self::InRange(lo, (lo + width - 1).min(MAX_ROLL))
+    }
+
+    pub fn is_range(&self) -> bool {
+        matches!(self, Self::InRange(..) | Self::OutOfRange(..))
+    }
+
+    /// Win chance in percent a range pins down, over/under bets pick theirs.
+    pub fn range_chance(&self) -> Option<f32> {
+        let width = |lo: u32, hi: u32| hi.saturating_sub(lo) + 1;
+
+        match *self {
+            Self::Over | Self::Under => None,
+            Self::InRange(lo, hi) => Some(width(lo, hi) as f32 / 100.),
+            Self::OutOfRange(lo, hi) => Some((MAX_ROLL + 1 - width(lo, hi)) as f32 / 100.),
+        }
+    }
+
+    /// Whether an over/under bet is high, sites without ranges reject the others.
+    pub fn high(&self, site: Sites) -> Result<bool, BetError> {
+        match self {
+            Self::Over => Ok(true),
+            Self::Under => Ok(false),
+            kind => Err(BetError::UnsupportedBetKind { site, kind: *kind }),
+        }
+    }
+
+    /// The roll the bet is decided at on a site rolling up to `max_roll`: over bets win from
+    /// it up, under bets below it and ranges start at it.
+    pub fn threshold(&self, chance: f32, max_roll: u32) -> u32 {
+        let threshold = chance_rolls(chance).min(max_roll);
+
+        match *self {
+            Self::Over => max_roll + 1 - threshold,
+            Self::Under => threshold,
+            Self::InRange(lo, _) | Self::OutOfRange(lo, _) => lo,
+        }
+    }
+
+    /// Whether `number` wins on a site rolling up to `max_roll`, `chance` only matters to
+    /// over/under bets.
+    pub fn wins(&self, number: u32, chance: f32, max_roll: u32) -> bool {
+        let threshold = chance_rolls(chance);
+
+        match *self {
+            Self::Over => number > max_roll - threshold.min(max_roll),
+            Self::Under => number < threshold,
+            Self::InRange(lo, hi) => (lo..=hi).contains(&number),
+            Self::OutOfRange(lo, hi) => !(lo..=hi).contains(&number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freebitcoin_rolls_up_to_10000() {
+        let max_roll = Sites::FreeBitcoIn.max_roll();
+        assert!(BetKind::Over.wins(10000, 47.5, max_roll));
+        assert!(
+            BetKind::Over.wins(5251, 47.5, max_roll) && !BetKind::Over.wins(5250, 47.5, max_roll)
+        );
+        assert_eq!(BetKind::Over.threshold(47.5, max_roll), 5251);
+        assert!(!BetKind::Under.wins(10000, 47.5, max_roll));
+    }
+
+    #[test]
+    fn ranges_fix_their_chance() {
+        assert_eq!(BetKind::bucket(4250., 100), BetKind::InRange(4200, 4299));
+        assert_eq!(BetKind::bucket(20000., 100), BetKind::InRange(9900, 9999));
+        assert_eq!(BetKind::InRange(4200, 4299).range_chance(), Some(1.));
+        assert_eq!(BetKind::OutOfRange(0, 4999).range_chance(), Some(50.));
+        assert_eq!(BetKind::Over.range_chance(), None);
+
+        assert_eq!(chance_rolls(0.29), 29);
+        assert!(
+            BetKind::Over.wins(9950, 0.5, MAX_ROLL) && !BetKind::Over.wins(9949, 0.5, MAX_ROLL)
+        );
+        assert!(BetKind::Under.wins(49, 0.5, MAX_ROLL) && !BetKind::Under.wins(50, 0.5, MAX_ROLL));
+        assert!(
+            BetKind::InRange(10, 20).wins(20, 0., MAX_ROLL)
+                && !BetKind::InRange(10, 20).wins(21, 0., MAX_ROLL)
+        );
+        assert!(BetKind::OutOfRange(10, 20).wins(9, 0., MAX_ROLL));
+        assert!(matches!(
+            BetKind::InRange(10, 20).high(Sites::Windice),
+            Err(BetError::UnsupportedBetKind { .. })
+        ));
+    }
+}
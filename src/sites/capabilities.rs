@@ -0,0 +1,157 @@
+use crate::sites::{BetDecision, BetKind};
+
+/// What a site accepts for one currency, see `SiteClient::capabilities`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SiteCapabilities {
+    pub min_bet: f32,
+    /// `None` when only the balance caps a bet.
+    pub max_bet: Option<f32>,
+    pub min_payout: f32,
+    pub max_payout: f32,
+    /// House edge in percent.
+    pub house_edge: f32,
+    /// The most a single bet may win.
+    pub max_profit: Option<f32>,
+    /// Decimal places amounts are sent with.
+    pub precision: u32,
+    /// Whether `BetKind::InRange` and `BetKind::OutOfRange` bets are taken.
+    pub ranges: bool,
+}
+
+/// The limits strategies assumed before sites reported their own.
+impl Default for SiteCapabilities {
+    fn default() -> Self {
+        Self {
+            min_bet: 1e-8,
+            max_bet: None,
+            min_payout: 1.01,
+            max_payout: 4750.,
+            house_edge: 1.,
+            max_profit: None,
+            precision: 8,
+            ranges: false,
+        }
+    }
+}
+
+impl SiteCapabilities {
+    /// Win chance in percent paying out `multiplier` after the house edge.
+    pub fn chance(&self, multiplier: f32) -> f32 {
+        (100. - self.house_edge) / multiplier
+    }
+
+    /// Payout multiplier of a `chance` percent bet after the house edge.
+    pub fn multiplier(&self, chance: f32) -> f32 {
+        (100. - self.house_edge) / chance
+    }
+
+    pub fn supports(&self, kind: &BetKind) -> bool {
+        self.ranges || !kind.is_range()
+    }
+
+    pub fn clamp_multiplier(&self, multiplier: f32) -> f32 {
+        multiplier.clamp(self.min_payout, self.max_payout)
+    }
+
+    pub fn clamp_chance(&self, chance: f32) -> f32 {
+        chance.clamp(self.chance(self.max_payout), self.chance(self.min_payout))
+    }
+
+    /// Rounds `amount` down to the site's precision, allowing for f32 noise so `0.01` doesn't
+    /// floor to `0.009999`.
+    pub fn round_amount(&self, amount: f32) -> f32 {
+        let scale = 10f64.powi(self.precision as i32);
+        let units = amount as f64 * scale * (1. + f32::EPSILON as f64);
+
+        (units.floor() / scale) as f32
+    }
+
+    /// The largest stake at `multiplier` the site takes.
+    pub fn max_amount(&self, multiplier: f32) -> Option<f32> {
+        let max_win = self
+            .max_profit
+            .filter(|_| multiplier > 1.)
+            .map(|max_profit| max_profit / (multiplier - 1.));
+
+        match (self.max_bet, max_win) {
+            (Some(max_bet), Some(max_win)) => Some(max_bet.min(max_win)),
+            (max_bet, max_win) => max_bet.or(max_win),
+        }
+    }
+
+    /// Fits a wager into the site's payout and stake limits, the chance follows a clamped
+    /// multiplier. A range pins the chance, so only its stake is fitted.
+    pub fn clamp(&self, wager: BetDecision) -> BetDecision {
+        let multiplier = if wager.kind.is_range() {
+            wager.multiplier
+        } else {
+            self.clamp_multiplier(wager.multiplier)
+        };
+        let chance = if multiplier == wager.multiplier {
+            wager.chance
+        } else {
+            self.chance(multiplier)
+        };
+        let amount = self
+            .max_amount(multiplier)
+            .map_or(wager.amount, |max_amount| wager.amount.min(max_amount));
+
+        BetDecision {
+            amount: self.round_amount(amount).max(self.min_bet),
+            chance,
+            multiplier,
+            kind: wager.kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wager(amount: f32, multiplier: f32) -> BetDecision {
+        BetDecision {
+            amount,
+            chance: 99. / multiplier,
+            multiplier,
+            kind: BetKind::Over,
+        }
+    }
+
+    #[test]
+    fn wagers_are_fitted_into_the_limits() {
+        let capabilities = SiteCapabilities {
+            min_bet: 1e-6,
+            max_bet: Some(0.5),
+            min_payout: 1.02,
+            max_payout: 9900.,
+            house_edge: 1.,
+            max_profit: Some(1.),
+            precision: 6,
+            ranges: false,
+        };
+
+        let fitted = capabilities.clamp(wager(0.1234567, 1.01));
+        assert_eq!(fitted.multiplier, 1.02);
+        assert!((fitted.chance - 99. / 1.02).abs() < 1e-4);
+        assert_eq!(fitted.amount, 0.123456);
+
+        assert_eq!(capabilities.clamp(wager(2., 1.5)).amount, 0.5);
+        let fitted = capabilities.clamp(wager(2., 11.));
+        assert!((fitted.amount - 0.1).abs() < 1e-6);
+        assert_eq!(capabilities.clamp(wager(1e-9, 2.)).amount, 1e-6);
+        assert_eq!(capabilities.clamp(wager(0.01, 2.)), wager(0.01, 2.));
+    }
+
+    #[test]
+    fn chances_follow_the_payout_range() {
+        let capabilities = SiteCapabilities {
+            min_payout: 99. / 95.,
+            max_payout: 9900.,
+            ..SiteCapabilities::default()
+        };
+        assert!((capabilities.clamp_chance(99.) - 95.).abs() < 1e-4);
+        assert!((capabilities.clamp_chance(0.) - 0.01).abs() < 1e-6);
+        assert_eq!(capabilities.round_amount(1e-8), 1e-8);
+    }
+}
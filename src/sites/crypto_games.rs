@@ -2,11 +2,15 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    sites::{BetError, BetResult, Site},
-    strategies::Strategy,
+    config::SiteConfig,
+    credentials::Secret,
+    sites::{
+        random_seed, read_json, request::Requester, BetDecision, BetError, BetOutcome,
+        CurrencyBalance, SeedInfo, SeedMap, SiteCapabilities, SiteClient, Sites,
+    },
 };
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Currency {
     BTC,
     ETH,
@@ -47,27 +51,68 @@ impl Currency {
             Self::PLAY => 20.,
         }
     }
+
+    /// The limits used until the coin's own are fetched.
+    pub fn capabilities(&self) -> SiteCapabilities {
+        SiteCapabilities {
+            min_bet: self.get_min_bet(),
+            max_bet: None,
+            min_payout: 1.02,
+            max_payout: 9900.,
+            house_edge: Sites::CryptoGames.house_edge(),
+            max_profit: None,
+            precision: 8,
+            ranges: Sites::CryptoGames.ranges(),
+        }
+    }
 }
 
-impl ToString for Currency {
-    fn to_string(&self) -> String {
-        match self {
-            Self::BTC => "BTC".to_string(),
-            Self::ETH => "ETH".to_string(),
-            Self::USDT => "USDT".to_string(),
-            Self::XRP => "XRP".to_string(),
-            Self::BNB => "BNB".to_string(),
-            Self::SOL => "SOL".to_string(),
-            Self::USDC => "USDC".to_string(),
-            Self::DOGE => "DOGE".to_string(),
-            Self::SHIB => "SHIB".to_string(),
-            Self::LTC => "LTC".to_string(),
-            Self::BCH => "BCH".to_string(),
-            Self::PEPE => "PEPE".to_string(),
-            Self::ETC => "ETC".to_string(),
-            Self::POL => "POL".to_string(),
-            Self::GAS => "GAS".to_string(),
-            Self::PLAY => "PLAY".to_string(),
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Self::BTC => "BTC",
+            Self::ETH => "ETH",
+            Self::USDT => "USDT",
+            Self::XRP => "XRP",
+            Self::BNB => "BNB",
+            Self::SOL => "SOL",
+            Self::USDC => "USDC",
+            Self::DOGE => "DOGE",
+            Self::SHIB => "SHIB",
+            Self::LTC => "LTC",
+            Self::BCH => "BCH",
+            Self::PEPE => "PEPE",
+            Self::ETC => "ETC",
+            Self::POL => "POL",
+            Self::GAS => "GAS",
+            Self::PLAY => "PLAY",
+        };
+
+        f.write_str(symbol)
+    }
+}
+
+impl TryFrom<&crate::currency::Currency> for Currency {
+    type Error = ();
+
+    fn try_from(value: &crate::currency::Currency) -> Result<Self, Self::Error> {
+        use crate::currency::Currency as TomlCurrency;
+
+        match value {
+            TomlCurrency::BTC => Ok(Self::BTC),
+            TomlCurrency::ETH => Ok(Self::ETH),
+            TomlCurrency::USDT => Ok(Self::USDT),
+            TomlCurrency::SOL => Ok(Self::SOL),
+            TomlCurrency::XRP => Ok(Self::XRP),
+            TomlCurrency::BNB => Ok(Self::BNB),
+            TomlCurrency::USDC => Ok(Self::USDC),
+            TomlCurrency::DOGE => Ok(Self::DOGE),
+            TomlCurrency::SHIB => Ok(Self::SHIB),
+            TomlCurrency::LTC => Ok(Self::LTC),
+            TomlCurrency::BCH => Ok(Self::BCH),
+            TomlCurrency::ETC => Ok(Self::ETC),
+            TomlCurrency::POL => Ok(Self::POL),
+            _ => Err(()),
         }
     }
 }
@@ -88,6 +133,21 @@ pub struct Coin {
     pub edge: f64,
 }
 
+impl From<Coin> for SiteCapabilities {
+    fn from(value: Coin) -> Self {
+        Self {
+            min_bet: value.min_bet as f32,
+            max_bet: None,
+            min_payout: value.min_payout as f32,
+            max_payout: value.max_payout as f32,
+            house_edge: value.edge as f32,
+            max_profit: Some(value.max_win as f32),
+            precision: 8,
+            ranges: Sites::CryptoGames.ranges(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct Bet {
     #[serde(rename(serialize = "Bet"))]
@@ -126,177 +186,239 @@ pub struct Balance {
     pub balance: f64,
 }
 
-#[derive(Debug, Clone)]
-pub struct UserStats {
-    pub balance: f32,
-}
-
-impl Default for UserStats {
-    fn default() -> Self {
-        Self { balance: 0. }
-    }
+#[derive(Debug, Deserialize, Serialize)]
+struct CryptoGamesState {
+    client_seed: String,
+    server_seed_hash: String,
+    #[serde(default)]
+    revealed: SeedMap,
 }
 
 pub struct CryptoGames {
-    pub rolls: u64,
     pub client_seed: String,
-    pub current_bet: f32,
-    pub multiplier: f32,
-    pub user_stats: UserStats,
-    pub profit: f32,
-    pub prediction: u32,
-    pub strategy: Box<dyn Strategy>,
+    server_seed_hash: String,
+    /// Every bet reveals the server seed it was rolled from.
+    revealed: SeedMap,
     client: reqwest::Client,
-    key: String,
-    history: Vec<BetResult>,
-    history_size: usize,
+    requester: Requester,
+    key: Secret,
     currency: Currency,
+    capabilities: SiteCapabilities,
 }
 
 impl Default for CryptoGames {
     fn default() -> Self {
-        let currency = Currency::PLAY;
-
         Self {
-            rolls: 0,
-            client_seed: "BeO2jZRd4nidPz4U40e2G7hT22s9GA".to_string(),
-            current_bet: currency.get_min_bet(),
-            multiplier: 2.,
-            user_stats: UserStats::default(),
-            profit: 0.,
-            prediction: 0,
-            strategy: Box::new(
-                // crate::strategies::blaks_runner::BlaksRunner5_0::default()
-                crate::strategies::my_strategy::MyStrat::default()
-                    // crate::strategies::none::NoStrat::default()
-                    .with_balance(0.00037203)
-                    .with_min_bet(currency.get_min_bet())
-                    .with_initial_bet(currency.get_min_bet()),
-            ),
+            client_seed: random_seed(30),
+            server_seed_hash: String::new(),
+            revealed: SeedMap::default(),
             client: reqwest::Client::new(),
-            key: "".to_string(),
-            history: Vec::new(),
-            history_size: 10,
-            currency,
+            requester: Requester::new(Sites::CryptoGames),
+            key: Secret::default(),
+            currency: Currency::PLAY,
+            capabilities: Currency::PLAY.capabilities(),
+        }
+    }
+}
+
+impl CryptoGames {
+    /// Replaces the built-in limits with the coin's, keeping them when the site doesn't answer.
+    async fn fetch_capabilities(&mut self) {
+        let coin = self
+            .requester
+            .send(self.client.get(format!(
+                "https://api.crypto.games/v1/coininfo/{}",
+                self.currency
+            )))
+            .await;
+        let coin: Result<Coin, BetError> = match coin {
+            Ok(coin) => read_json(coin).await,
+            Err(err) => Err(err),
+        };
+        match coin {
+            Ok(coin) => self.capabilities = coin.into(),
+            Err(err) => eprintln!("Using the built-in {} limits: {err}", self.currency),
         }
     }
 }
 
 #[async_trait]
-impl Site for CryptoGames {
-    async fn login(&mut self) -> Result<(), BetError> {
-        let balance: Balance = self
-            .client
-            .get(format!(
-                "https://api.crypto.games/v1/balance/{}/{}",
-                self.currency.to_string(),
-                self.key
-            ))
-            .send()
-            .await?
-            .json()
-            .await?;
+impl SiteClient for CryptoGames {
+    fn site(&self) -> Sites {
+        Sites::CryptoGames
+    }
 
-        self.user_stats.balance = balance.balance as f32;
-        self.strategy.set_balance(self.user_stats.balance);
+    fn symbol(&self) -> String {
+        self.currency.to_string()
+    }
 
-        Ok(())
+    fn capabilities(&self) -> SiteCapabilities {
+        self.capabilities.clone()
     }
 
-    async fn do_bet(&mut self, prediction: f32, confidence: f32) -> Result<BetResult, BetError> {
-        self.rolls += 1;
-        let next_bet_data = self.strategy.get_next_bet(prediction, confidence);
-        self.current_bet = next_bet_data.0;
-        self.multiplier = next_bet_data.1;
-        let high = next_bet_data.3;
+    fn odds(&self, _chance: f32, multiplier: f32) -> (f32, f32) {
+        let multiplier = self.capabilities.clamp_multiplier(multiplier);
 
-        if self.history.len() < self.history_size {
-            self.current_bet = self.currency.get_min_bet();
-            self.multiplier = 2.;
-        }
+        (self.capabilities.chance(multiplier), multiplier)
+    }
 
-        self.multiplier = self.multiplier.clamp(1.02, 9900.);
-        self.current_bet = self.current_bet.max(self.currency.get_min_bet());
-
-        let res: serde_json::Value = self
-            .client
-            .post(format!(
-                "https://api.crypto.games/v1/placebet/{}/{}",
-                self.currency.to_string(),
-                self.key
-            ))
-            .json(&Bet {
-                bet: self.current_bet as f64,
-                payout: self.multiplier as f64,
-                under_over: high,
-                client_seed: self.client_seed.clone(),
-            })
-            .send()
-            .await?
-            .json()
-            .await?;
+    /// Bets are authenticated by the key in the URL, logging in only fetches the coin's limits.
+    async fn login(&mut self) -> Result<(), BetError> {
+        self.fetch_capabilities().await;
 
-        let mut res: BetSiteResult = serde_json::from_value(res).unwrap();
-        res.roll *= 100.;
+        Ok(())
+    }
 
-        self.history.push(res.clone().into());
-        if self.history.len() > self.history_size {
-            self.history = self.history[1..].to_vec();
-        }
+    /// The next coin's server seed commitment is only known once a bet in it is placed.
+    async fn switch_currency(
+        &mut self,
+        currency: &crate::currency::Currency,
+    ) -> Result<(), BetError> {
+        let currency = Currency::try_from(currency).map_err(|_| BetError::UnsupportedCurrency {
+            site: Sites::CryptoGames,
+            currency: currency.clone(),
+        })?;
+        self.capabilities = currency.capabilities();
+        self.currency = currency;
+        self.server_seed_hash.clear();
+        self.fetch_capabilities().await;
 
-        if self.current_bet > self.strategy.get_balance() {
-            panic!("Not enough money!");
-        }
+        Ok(())
+    }
 
-        Ok(res.into())
+    async fn place_bet(&mut self, wager: &BetDecision) -> Result<BetOutcome, BetError> {
+        let high = wager.kind.high(Sites::CryptoGames)?;
+        let res = self
+            .requester
+            .send_bet(
+                self.client
+                    .post(format!(
+                        "https://api.crypto.games/v1/placebet/{}/{}",
+                        self.currency,
+                        self.key.expose()
+                    ))
+                    .json(&Bet {
+                        bet: wager.amount as f64,
+                        payout: wager.multiplier as f64,
+                        under_over: high,
+                        client_seed: self.client_seed.clone(),
+                    }),
+            )
+            .await?;
+        let res: serde_json::Value = read_json(res).await?;
+
+        let res: BetSiteResult = match serde_json::from_value(res.clone()) {
+            Ok(res) => res,
+            // Rejected bets only carry a `Message`.
+            Err(_) => match res["Message"].as_str() {
+                Some(reason) => {
+                    return Err(BetError::BetRejected {
+                        reason: reason.to_string(),
+                    })
+                }
+                None => {
+                    return Err(BetError::Parse {
+                        raw: res.to_string(),
+                    })
+                }
+            },
+        };
+        // The first bet's commitment is only known once it's been placed.
+        let server_seed_hash = std::mem::replace(
+            &mut self.server_seed_hash,
+            res.next_server_seed_hash.clone(),
+        );
+        self.revealed
+            .insert(server_seed_hash, res.server_seed.clone());
+
+        let mut bet_result: BetOutcome = res.into();
+        bet_result.client_seed = self.client_seed.clone();
+        bet_result.symbol = self.currency.to_string();
+
+        Ok(bet_result.complete(wager, Sites::CryptoGames))
     }
 
-    fn on_win(&mut self, bet_result: &BetResult) {
-        self.user_stats.balance += bet_result.win_amount;
-        self.profit += bet_result.win_amount;
+    async fn fetch_balances(&mut self) -> Result<Vec<CurrencyBalance>, BetError> {
+        let balance = self
+            .requester
+            .send(self.client.get(format!(
+                "https://api.crypto.games/v1/balance/{}/{}",
+                self.currency,
+                self.key.expose()
+            )))
+            .await?;
+        let balance: Balance = read_json(balance).await?;
 
-        if self.history.len() >= self.history_size {
-            self.strategy.on_win(bet_result);
-        }
+        Ok(vec![CurrencyBalance {
+            symbol: self.currency.to_string(),
+            amount: balance.balance as f32,
+        }])
     }
 
-    fn on_lose(&mut self, bet_result: &BetResult) {
-        let mut bet_result = bet_result.clone();
-        bet_result.win_amount = -bet_result.win_amount;
+    /// The client seed travels with every bet and the server seed changes after each one, so
+    /// only the client seed needs replacing.
+    async fn rotate_seed(&mut self) -> Result<(), BetError> {
+        self.client_seed = random_seed(30);
 
-        self.user_stats.balance -= bet_result.win_amount;
-        self.profit -= bet_result.win_amount;
+        Ok(())
+    }
 
-        if self.history.len() >= self.history_size {
-            self.strategy.on_lose(&bet_result);
+    fn seed_info(&self) -> SeedInfo {
+        SeedInfo {
+            server_seed_hash: self.server_seed_hash.clone(),
+            client_seed: self.client_seed.clone(),
+            nonce: 0,
         }
     }
 
-    fn get_history(&self) -> Vec<BetResult> {
-        self.history.clone()
+    async fn fetch_revealed_seed(&mut self, server_seed_hash: &str) -> Result<String, BetError> {
+        self.revealed
+            .get(server_seed_hash)
+            .map(str::to_string)
+            .ok_or_else(|| BetError::SeedUnavailable {
+                server_seed_hash: server_seed_hash.to_string(),
+            })
     }
 
-    fn get_rolls(&self) -> u64 {
-        self.rolls
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(CryptoGamesState {
+            client_seed: self.client_seed.clone(),
+            server_seed_hash: self.server_seed_hash.clone(),
+            revealed: self.revealed.clone(),
+        })
+        .unwrap_or_default()
     }
 
-    fn get_current_bet(&self) -> f32 {
-        self.current_bet
-    }
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        let state: CryptoGamesState = serde_json::from_value(state)?;
 
-    fn get_current_multiplier(&self) -> f32 {
-        self.multiplier
-    }
+        self.client_seed = state.client_seed;
+        self.server_seed_hash = state.server_seed_hash;
+        self.revealed = state.revealed;
 
-    fn get_history_size(&self) -> usize {
-        self.history_size
+        Ok(())
     }
+}
 
-    fn get_profit(&self) -> f32 {
-        self.profit
+impl SiteConfig for CryptoGames {
+    fn with_api_key(mut self, api_key: Secret) -> Self
+    where
+        Self: Sized,
+    {
+        self.key = api_key;
+
+        self
     }
 
-    fn get_balance(&self) -> f32 {
-        self.user_stats.balance
+    fn with_currency(mut self, currency: crate::currency::Currency) -> Self
+    where
+        Self: Sized,
+    {
+        if let Ok(currency) = Currency::try_from(&currency) {
+            self.capabilities = currency.capabilities();
+            self.currency = currency;
+        }
+
+        self
     }
 }
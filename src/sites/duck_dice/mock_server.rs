@@ -0,0 +1,351 @@
+//! A local DuckDice API with real provably-fair rolls and per-currency balances, for driving
+//! `DuckDiceIo` end to end.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::sites::mock::{MockServer, Request, Response};
+use crate::sites::provably_fair::{DuckDiceRoll, RollAlgorithm};
+use crate::sites::random_seed;
+
+pub const API_KEY: &str = "mock-api-key";
+
+/// A failure served instead of the next replies.
+#[derive(Clone, Copy, Debug)]
+pub enum Fault {
+    /// Cloudflare turning the request away.
+    Forbidden,
+    RateLimited {
+        retry_after: u64,
+    },
+    MalformedJson,
+    /// The API key rejected as if the session expired.
+    Unauthenticated,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Balance {
+    pub main: f64,
+    pub faucet: f64,
+}
+
+/// A seed pair, `server_seed` stays secret until the pair is retired.
+#[derive(Clone, Debug)]
+pub struct Seeds {
+    pub server_seed: String,
+    pub server_seed_hash: String,
+    pub client_seed: String,
+    pub nonce: u64,
+}
+
+impl Seeds {
+    fn new(client_seed: String) -> Self {
+        let server_seed = random_seed(64);
+
+        Self {
+            server_seed_hash: hex::encode(Sha256::digest(server_seed.as_bytes())),
+            server_seed,
+            client_seed,
+            nonce: 0,
+        }
+    }
+}
+
+struct PlacedBet {
+    bet: Value,
+    server_seed_hash: String,
+    client_seed: String,
+    nonce: u64,
+}
+
+#[derive(Default)]
+struct State {
+    balances: HashMap<String, Balance>,
+    seeds: Option<Seeds>,
+    /// Server seeds of the retired pairs by hash.
+    retired: HashMap<String, String>,
+    bets: HashMap<String, PlacedBet>,
+    faults: Vec<Fault>,
+    /// Reveal another seed than the one committed to, the way a rigged site would.
+    dishonest: bool,
+    bet_count: u64,
+    wins: u64,
+}
+
+pub struct MockDuckDice {
+    pub server: MockServer,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockDuckDice {
+    pub async fn start(balances: &[(&str, Balance)]) -> Self {
+        let state = Arc::new(Mutex::new(State {
+            balances: balances
+                .iter()
+                .map(|(symbol, balance)| (symbol.to_string(), balance.clone()))
+                .collect(),
+            seeds: Some(Seeds::new("mock-client-seed".to_string())),
+            ..State::default()
+        }));
+
+        let handler_state = Arc::clone(&state);
+        let server =
+            MockServer::start(move |request| handle(&mut handler_state.lock().unwrap(), request))
+                .await;
+
+        Self { server, state }
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("{}/api", self.server.base_url)
+    }
+
+    /// Serves `fault` for the next `times` requests, whatever their endpoint.
+    pub fn fail_next(&self, fault: Fault, times: usize) {
+        let mut state = self.state.lock().unwrap();
+        for _ in 0..times {
+            state.faults.push(fault);
+        }
+    }
+
+    pub fn balance(&self, symbol: &str) -> Balance {
+        self.state.lock().unwrap().balances[symbol].clone()
+    }
+
+    /// Moves the balance outside the API, the way another client would.
+    pub fn adjust_balance(&self, symbol: &str, faucet: f64) {
+        self.state
+            .lock()
+            .unwrap()
+            .balances
+            .get_mut(symbol)
+            .unwrap()
+            .faucet += faucet;
+    }
+
+    /// Drops a bet from the site's records.
+    pub fn forget_bet(&self, hash: &str) {
+        self.state.lock().unwrap().bets.remove(hash);
+    }
+
+    /// Reveals a fresh seed in place of the committed one from now on.
+    pub fn reveal_other_seeds(&self) {
+        self.state.lock().unwrap().dishonest = true;
+    }
+
+    pub fn seeds(&self) -> Seeds {
+        self.state.lock().unwrap().seeds.clone().unwrap()
+    }
+}
+
+fn handle(state: &mut State, request: &Request) -> Response {
+    if !state.faults.is_empty() {
+        return match state.faults.remove(0) {
+            Fault::Forbidden => Response::raw(403, "<html>Attention Required!</html>"),
+            Fault::RateLimited { retry_after } => {
+                Response::json(429, json!({ "error": "Too many requests" }))
+                    .with_header("Retry-After", &retry_after.to_string())
+            }
+            Fault::MalformedJson => Response::raw(200, r#"{"bet": {"hash": "#),
+            Fault::Unauthenticated => Response::json(401, json!({ "error": "Unauthenticated." })),
+        };
+    }
+
+    let (path, query) = request
+        .path
+        .split_once('?')
+        .unwrap_or((request.path.as_str(), ""));
+    let authorized = query
+        .split('&')
+        .any(|pair| pair == format!("api_key={API_KEY}"));
+
+    match (request.method.as_str(), path) {
+        ("GET", path) if path.starts_with("/api/bet/") => bet_info(state, &path[9..]),
+        (_, "/api/play" | "/api/bot/user-info" | "/api/randomize") if !authorized => {
+            Response::json(401, json!({ "error": "Unauthenticated." }))
+        }
+        ("POST", "/api/play") => play(state, &request.json()),
+        ("GET", "/api/bot/user-info") => user_info(state),
+        ("POST", "/api/randomize") => randomize(state, &request.json()),
+        _ => Response::json(404, json!({ "error": "Not found" })),
+    }
+}
+
+fn play(state: &mut State, body: &Value) -> Response {
+    let (Some(symbol), Some(chance), Some(is_high), Some(amount)) = (
+        body["symbol"].as_str(),
+        body["chance"].as_f64(),
+        body["isHigh"].as_bool(),
+        body["amount"].as_f64(),
+    ) else {
+        return Response::json(422, json!({ "error": "Invalid bet" }));
+    };
+    let faucet = body["faucet"].as_bool().unwrap_or(false);
+    if !(0.01..=98.).contains(&chance) || amount <= 0. {
+        return Response::json(422, json!({ "error": "Invalid chance or amount" }));
+    }
+
+    let Some(balance) = state.balances.get_mut(symbol) else {
+        return Response::json(422, json!({ "error": "Unknown currency" }));
+    };
+    let available = if faucet {
+        &mut balance.faucet
+    } else {
+        &mut balance.main
+    };
+    if amount > *available + 1e-12 {
+        return Response::json(422, json!({ "error": "Insufficient funds" }));
+    }
+
+    let seeds = state.seeds.as_mut().unwrap();
+    let nonce = seeds.nonce;
+    let number = DuckDiceRoll.roll(&seeds.server_seed, &seeds.client_seed, nonce);
+    seeds.nonce += 1;
+
+    let width = (chance * 100.).round() as u32;
+    let (choice, result) = if is_high {
+        (format!(">{}", 9999 - width), number > 9999 - width)
+    } else {
+        (format!("<{width}"), number < width)
+    };
+    let payout = 99. / chance;
+    let win_amount = if result { amount * payout } else { 0. };
+    *available += win_amount - amount;
+    let balance = *available;
+
+    state.bet_count += 1;
+    if result {
+        state.wins += 1;
+    }
+
+    let hash = random_seed(10).to_lowercase();
+    let bet = json!({
+        "hash": hash,
+        "symbol": symbol,
+        "choice": choice,
+        "result": result,
+        "number": number,
+        "chance": chance,
+        "payout": payout,
+        "betAmount": format!("{amount:.8}"),
+        "winAmount": format!("{win_amount:.8}"),
+        "profit": format!("{:.8}", win_amount - amount),
+        "mined": null,
+        "nonce": nonce,
+        "created": 1_700_000_000_000u64 + state.bet_count,
+        "gameMode": if faucet { "faucet" } else { "main" },
+    });
+    state.bets.insert(
+        hash,
+        PlacedBet {
+            bet: bet.clone(),
+            server_seed_hash: seeds.server_seed_hash.clone(),
+            client_seed: seeds.client_seed.clone(),
+            nonce,
+        },
+    );
+
+    Response::json(
+        200,
+        json!({
+            "bet": bet,
+            "isJackpot": false,
+            "jackpotStatus": null,
+            "jackpot": null,
+            "user": {
+                "hash": "mockuser",
+                "level": 1,
+                "username": "mock",
+                "bets": state.bet_count,
+                "nonce": nonce + 1,
+                "wins": state.wins,
+                "luck": 100.,
+                "balance": format!("{balance:.8}"),
+                "profit": "0",
+                "volume": "0",
+                "absoluteLevel": { "level": 1, "xp": 0, "xpNext": 100, "xpPrev": 0 },
+            },
+        }),
+    )
+}
+
+fn user_info(state: &State) -> Response {
+    let balances = state
+        .balances
+        .iter()
+        .map(|(symbol, balance)| {
+            json!({
+                "currency": symbol,
+                "main": format!("{:.8}", balance.main),
+                "faucet": format!("{:.8}", balance.faucet),
+                "affiliate": null,
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    Response::json(
+        200,
+        json!({
+            "hash": "mockuser",
+            "username": "mock",
+            "createdAt": 1_700_000_000u64,
+            "level": 1,
+            "campaign": null,
+            "affiliate": null,
+            "lastDeposit": null,
+            "wagered": [],
+            "balances": balances,
+            "wageringBonuses": [],
+            "tle": [],
+        }),
+    )
+}
+
+/// Retires the active pair, its server seed is revealed from now on.
+fn randomize(state: &mut State, body: &Value) -> Response {
+    let Some(client_seed) = body["clientSeed"].as_str() else {
+        return Response::json(422, json!({ "error": "clientSeed is required" }));
+    };
+
+    let retired = state
+        .seeds
+        .replace(Seeds::new(client_seed.to_string()))
+        .unwrap();
+    let server_seed = if state.dishonest {
+        random_seed(64)
+    } else {
+        retired.server_seed
+    };
+    state.retired.insert(retired.server_seed_hash, server_seed);
+
+    let seeds = state.seeds.as_ref().unwrap();
+    Response::json(
+        200,
+        json!({
+            "clientSeed": seeds.client_seed,
+            "serverSeedHash": seeds.server_seed_hash,
+        }),
+    )
+}
+
+fn bet_info(state: &State, hash: &str) -> Response {
+    let Some(placed) = state.bets.get(hash) else {
+        return Response::json(404, json!({ "error": "Bet not found" }));
+    };
+
+    Response::json(
+        200,
+        json!({
+            "bet": placed.bet,
+            "seed": {
+                "serverSeedHash": placed.server_seed_hash,
+                "clientSeed": placed.client_seed,
+                "serverSeed": state.retired.get(&placed.server_seed_hash),
+                "nonce": placed.nonce,
+            },
+        }),
+    )
+}
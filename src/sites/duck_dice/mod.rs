@@ -1,12 +1,10 @@
-use std::time::Duration;
-
 use async_trait::async_trait;
-use rand::Rng;
 use reqwest::header::{
     AUTHORIZATION, SERVER, STRICT_TRANSPORT_SECURITY, USER_AGENT, X_CONTENT_TYPE_OPTIONS,
     X_FRAME_OPTIONS,
 };
 use reqwest::redirect::Policy;
+use reqwest::StatusCode;
 use reqwest::{
     header::{HeaderMap, CACHE_CONTROL, CONTENT_TYPE},
     Url,
@@ -14,13 +12,21 @@ use reqwest::{
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use crate::config::{SiteConfig, TomlStrategies};
+use crate::config::SiteConfig;
+use crate::credentials::Secret;
 use crate::currency::Currency;
-use crate::sites::fake_test::{duckdice_fake_bet, reset_server_seed};
-use crate::sites::{BetError, BetResult, Site, Sites};
-use crate::strategies::Strategy;
+use crate::sites::ledger::SiteBet;
+use crate::sites::{
+    random_seed, read_json, request::Requester, BetDecision, BetError, BetOutcome, CurrencyBalance,
+    SeedInfo, SeedMap, SiteCapabilities, SiteClient, Sites,
+};
 
-const API_KEY: &str = "";
+#[cfg(test)]
+mod mock_server;
+#[cfg(test)]
+mod tests;
+
+const BASE_URL: &str = "https://duckdice.io/api";
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Jackpot {
@@ -60,6 +66,8 @@ pub struct BetJson {
 
 #[derive(Clone, Debug)]
 pub struct Bet {
+    /// The bet's own hash, `hash` and `previous_hash` end up holding server seed hashes.
+    pub id: String,
     pub previous_hash: String,
     pub hash: String,
     pub symbol: String,
@@ -80,6 +88,7 @@ pub struct Bet {
 impl From<BetJson> for Bet {
     fn from(value: BetJson) -> Self {
         Bet {
+            id: value.hash.clone(),
             previous_hash: value.hash.clone(),
             hash: value.hash.clone(),
             symbol: value.symbol.clone(),
@@ -344,381 +353,178 @@ impl From<UserInfoJson> for UserInfo {
     }
 }
 
+/// Seed state persisted between runs.
+#[derive(Debug, Deserialize, Serialize)]
+struct DuckDiceState {
+    server_seed_hash: String,
+    #[serde(default)]
+    previous_hash: String,
+    client_seed: String,
+    nonce: u64,
+    initialized_hash: bool,
+    #[serde(default)]
+    bets: SeedMap,
+    #[serde(default)]
+    revealed: SeedMap,
+}
+
 pub struct DuckDiceIo {
     pub client: reqwest::Client,
-    pub rolls: u64,
-    pub strategy: Box<dyn Strategy>,
-    history: Vec<BetResult>,
-    history_size: usize,
-    current_bet: f32,
-    chance: f32,
-    balance: f32,
-    offline_balance: f32,
-    initial_balance: f32,
-    site_balance: f32,
-    profit: f32,
+    requester: Requester,
+    base_url: String,
+    server_seed_hash: String,
+    /// The retired pair's hash, bets carry it as `hash_previous_roll`.
     previous_hash: String,
     client_seed: String,
+    nonce: u64,
     default_headers: HeaderMap,
     currency: Currency,
     faucet: bool,
     initialized_hash: bool,
-    wins: u32,
-    losses: u32,
-    seed_profit: f32,
-    api_key: String,
-    use_site_balance: bool,
-    balance_modifier: f32,
-    use_fake_betting: bool,
+    /// A bet of each pair, its details carry the server seed once the pair is retired.
+    bets: SeedMap,
+    revealed: SeedMap,
+    api_key: Secret,
     tle_hash: Option<String>,
 }
 
 impl Default for DuckDiceIo {
     fn default() -> Self {
-        let currency = Currency::ETH;
-
         Self {
             client: reqwest::Client::new(),
-            rolls: 0,
-            strategy: Box::new(
-                // crate::strategies::blaks_runner::BlaksRunner5_0::default()
-                // crate::strategies::my_strategy::MyStrat::default()
-                crate::strategies::none::NoStrat::default()
-                    .with_balance(4.)
-                    .with_min_bet(currency.get_min_bet(Sites::DuckDiceIo))
-                    .with_initial_bet(currency.get_min_bet(Sites::DuckDiceIo)),
-            ),
-            history: Vec::new(),
-            history_size: 10,
-            current_bet: currency.get_min_bet(Sites::DuckDiceIo),
-            chance: 2.,
-            balance: 3.,
-            offline_balance: 10.,
-            initial_balance: 0.,
-            site_balance: 0.,
-            profit: 0.,
+            requester: Requester::new(Sites::DuckDiceIo),
+            base_url: BASE_URL.to_string(),
+            server_seed_hash: String::new(),
             previous_hash: String::new(),
             client_seed: String::new(),
+            nonce: 0,
             default_headers: HeaderMap::new(),
-            currency,
+            currency: Currency::ETH,
             faucet: true,
             initialized_hash: false,
-            wins: 0,
-            losses: 0,
-            seed_profit: 0.,
-            api_key: String::new(),
-            use_site_balance: true,
-            balance_modifier: 1.,
-            use_fake_betting: false,
+            bets: SeedMap::default(),
+            revealed: SeedMap::default(),
+            api_key: Secret::default(),
             tle_hash: None,
         }
     }
 }
 
-#[async_trait]
-impl Site for DuckDiceIo {
-    async fn login(&mut self) -> Result<(), BetError> {
-        self.default_headers
-            .insert(CONTENT_TYPE, "application/json".parse().unwrap());
-        self.default_headers
-            .insert(CACHE_CONTROL, "no-cache, private".parse().unwrap());
-        self.default_headers.insert(
-            STRICT_TRANSPORT_SECURITY,
-            "max-age=15552000; includeSubDomains".parse().unwrap(),
-        );
-        self.default_headers
-            .insert(X_CONTENT_TYPE_OPTIONS, "nosniff".parse().unwrap());
-        self.default_headers
-            .insert(X_FRAME_OPTIONS, "sameorigin".parse().unwrap());
-        self.default_headers
-            .insert(SERVER, "cloudflare".parse().unwrap());
-        self.default_headers.insert(USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36".parse().unwrap());
+impl DuckDiceIo {
+    /// Points the client at another API root, e.g. a local mock server.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+
+        self
+    }
 
+    pub fn with_requester(mut self, requester: Requester) -> Self {
+        self.requester = requester;
+
+        self
+    }
+
+    fn build_client(&mut self) -> Result<(), BetError> {
         self.client = reqwest::Client::builder()
             .default_headers(self.default_headers.clone())
             .user_agent("DuckDiceBot/1.0.0")
             .redirect(Policy::limited(200))
             .build()?;
 
-        if self.use_site_balance {
-            let user_info_url = Url::parse(&format!(
-                "https://duckdice.io/api/bot/user-info?api_key={API_KEY}",
-            ))
-            .expect("Failed to parse user_info URL");
-            let res: serde_json::Value =
-                self.client.get(user_info_url).send().await?.json().await?;
-            let res: UserInfoJson = serde_json::from_value(res).unwrap();
-            let res: UserInfo = res.into();
-
-            for balance in &res.balances {
-                if balance.currency == self.currency.to_string().as_str() {
-                    if let Some(main) = &if self.faucet {
-                        balance.faucet.clone()
-                    } else {
-                        balance.main.clone()
-                    } {
-                        let val = main.parse::<f32>().unwrap_or(0.);
-                        self.strategy.set_balance(val * self.balance_modifier);
-                        self.site_balance = val;
-                        self.balance = val * self.balance_modifier;
-                        self.initial_balance = val * self.balance_modifier;
-                    }
-                }
-            }
-        } else {
-            self.strategy
-                .set_balance(self.offline_balance * self.balance_modifier);
-            self.site_balance = self.offline_balance;
-            self.balance = self.offline_balance * self.balance_modifier;
-            self.initial_balance = self.offline_balance * self.balance_modifier;
-        }
-
         Ok(())
     }
 
-    async fn do_bet(&mut self, prediction: f32, confidence: f32) -> Result<BetResult, BetError> {
-        if self.balance >= self.initial_balance * 10. {
-            if self.use_site_balance {
-                println!("[WIN] Resetting {:0>.8}", self.site_balance);
-                self.balance = self.site_balance * self.balance_modifier;
-                self.initial_balance = self.site_balance * self.balance_modifier;
-            } else {
-                println!("[WIN] Resetting {:0>.8}", self.offline_balance);
-                self.balance = self.offline_balance * self.balance_modifier;
-                self.initial_balance = self.offline_balance * self.balance_modifier;
-            }
-            self.strategy.reset();
-
-            if self.profit > 0. && self.use_site_balance {
-                let user_info_url = Url::parse(&format!(
-                    "https://duckdice.io/api/bot/user-info?api_key={API_KEY}",
-                ))
-                .expect("Failed to parse user_info URL");
-                let res: serde_json::Value =
-                    self.client.get(user_info_url).send().await?.json().await?;
-                let res: UserInfoJson = serde_json::from_value(res).unwrap();
-                let res: UserInfo = res.into();
-
-                for balance in &res.balances {
-                    if balance.currency == self.currency.to_string().as_str() {
-                        if let Some(main) = &if self.faucet {
-                            balance.faucet.clone()
-                        } else {
-                            balance.main.clone()
-                        } {
-                            let val = main.parse::<f32>().unwrap_or(0.);
-                            self.strategy.set_balance(val * self.balance_modifier);
-                            self.site_balance = val;
-                            self.balance = val * self.balance_modifier;
-                            self.initial_balance = val * self.balance_modifier;
-                            self.strategy.reset();
-                            self.profit = 0.;
-                        }
-                    }
-                }
-            } else if self.profit > 0. && !self.use_site_balance {
-                self.strategy
-                    .set_balance(self.offline_balance * self.balance_modifier);
-                self.balance = self.offline_balance * self.balance_modifier;
-                self.initial_balance = self.offline_balance * self.balance_modifier;
-                self.strategy.reset();
-                self.profit = 0.;
-            }
-        }
+    /// A placed bet and the seed pair it was rolled from.
+    async fn fetch_bet(&self, bet_hash: &str) -> Result<serde_json::Value, BetError> {
+        let res = self
+            .requester
+            .send(self.client.get(format!("{}/bet/{bet_hash}", self.base_url)))
+            .await?;
 
-        if self.balance - self.current_bet <= 0. && self.use_site_balance {
-            println!("[FAIL] Resetting {:0>.8}", self.site_balance);
-            self.balance = self.site_balance * self.balance_modifier;
-            self.initial_balance = self.site_balance * self.balance_modifier;
-            self.wins = 0;
-            self.losses = 0;
-            self.seed_profit = 0.;
-            self.strategy.reset();
-            let randomize_url = Url::parse_with_params(
-                "https://duckdice.io/api/randomize",
-                &[("api_key", API_KEY)],
-            )
-            .expect("Failed to parse randomize URL");
-
-            self.client_seed = rand::rng()
-                .sample_iter(rand::distr::Alphabetic)
-                .take(30)
-                .map(char::from)
-                .collect();
-            let res_randomize = self
-                .client
-                .post(randomize_url)
-                .json(&json!({
-                    "clientSeed": self.client_seed.clone(),
-                }))
-                .send()
-                .await?;
-
-            if let Some(retry_after) = res_randomize.headers().get("retry-after") {
-                let _ = tokio::time::sleep(Duration::from_secs(
-                    retry_after.to_str().unwrap().parse::<u64>().unwrap(),
-                ))
-                .await;
-            }
-            self.initialized_hash = false;
-            let user_info_url = Url::parse(&format!(
-                "https://duckdice.io/api/bot/user-info?api_key={API_KEY}",
-            ))
-            .expect("Failed to parse user_info URL");
-            let res: serde_json::Value =
-                self.client.get(user_info_url).send().await?.json().await?;
-            let res: UserInfoJson = serde_json::from_value(res).unwrap();
-            let res: UserInfo = res.into();
-
-            for balance in &res.balances {
-                if balance.currency == self.currency.to_string().as_str() {
-                    if let Some(main) = &if self.faucet {
-                        balance.faucet.clone()
-                    } else {
-                        balance.main.clone()
-                    } {
-                        let _val = main.parse::<f32>().unwrap_or(0.);
-                        self.strategy
-                            .set_balance(self.site_balance * self.balance_modifier);
-                        self.balance = self.site_balance * self.balance_modifier;
-                        self.initial_balance = self.site_balance * self.balance_modifier;
-                        self.strategy.reset();
-                    }
-                }
-            }
-        } else if self.balance - self.current_bet <= 0. && !self.use_site_balance {
-            println!("[FAIL] Resetting {:0>.8}", self.offline_balance);
-            reset_server_seed();
-            self.balance = self.offline_balance * self.balance_modifier;
-            self.initial_balance = self.offline_balance * self.balance_modifier;
-            self.wins = 0;
-            self.losses = 0;
-            self.seed_profit = 0.;
-            self.strategy.reset();
+        read_json(res).await
+    }
+
+    /// The seed pair a bet was rolled from.
+    async fn fetch_bet_seed(&self, bet_hash: &str) -> Result<serde_json::Value, BetError> {
+        let mut res = self.fetch_bet(bet_hash).await?;
+
+        match res["seed"].take() {
+            serde_json::Value::Object(seed) => Ok(serde_json::Value::Object(seed)),
+            _ => Err(BetError::Parse {
+                raw: res.to_string(),
+            }),
         }
+    }
+}
 
-        self.rolls += 1;
-        let next_bet_data = self.strategy.get_next_bet(prediction, confidence);
-        self.current_bet = next_bet_data.0;
-        self.chance = next_bet_data.2;
-        let high = next_bet_data.3;
+#[async_trait]
+impl SiteClient for DuckDiceIo {
+    fn site(&self) -> Sites {
+        Sites::DuckDiceIo
+    }
 
-        self.chance = self.chance.max(2.);
+    fn symbol(&self) -> String {
+        self.currency.to_string()
+    }
 
-        if self.history.len() < self.history_size {
-            self.current_bet = self.currency.get_min_bet(Sites::DuckDiceIo);
-            self.chance = 50.;
+    /// Chances run from 0.01 to 98 percent.
+    fn capabilities(&self) -> SiteCapabilities {
+        let house_edge = Sites::DuckDiceIo.house_edge();
+
+        SiteCapabilities {
+            min_bet: self.currency.get_min_bet(Sites::DuckDiceIo),
+            max_bet: None,
+            min_payout: (100. - house_edge) / 98.,
+            max_payout: (100. - house_edge) / 0.01,
+            house_edge,
+            max_profit: None,
+            precision: 8,
+            ranges: Sites::DuckDiceIo.ranges(),
         }
+    }
 
-        self.current_bet = self
-            .current_bet
-            .max(self.currency.get_min_bet(Sites::DuckDiceIo));
-
-        let bet_url =
-            Url::parse_with_params("https://duckdice.io/api/play", &[("api_key", API_KEY)])
-                .expect("Failed to parse do_bet URL");
-
-        if self.use_fake_betting {
-            let bet_result = duckdice_fake_bet(
-                high,
-                "BeO2jZRd4nidPz4U40e2G7hT22s9GA",
-                self.current_bet,
-                next_bet_data.1,
-            );
-
-            self.history.push(bet_result.clone().into());
-            if self.history.len() > self.history_size {
-                self.history = self.history[1..].to_vec();
-            }
-
-            if self.current_bet > self.site_balance {
-                self.current_bet = self.currency.get_min_bet(Sites::DuckDiceIo);
-            }
+    fn odds(&self, chance: f32, _multiplier: f32) -> (f32, f32) {
+        let capabilities = self.capabilities();
+        let chance = capabilities.clamp_chance(chance);
 
-            if self.current_bet > self.site_balance {
-                panic!("Insufficient Balance");
-            }
+        (chance, capabilities.multiplier(chance))
+    }
 
-            return Ok(bet_result.into());
-        }
+    async fn login(&mut self) -> Result<(), BetError> {
+        self.default_headers
+            .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        self.default_headers
+            .insert(CACHE_CONTROL, "no-cache, private".parse().unwrap());
+        self.default_headers.insert(
+            STRICT_TRANSPORT_SECURITY,
+            "max-age=15552000; includeSubDomains".parse().unwrap(),
+        );
+        self.default_headers
+            .insert(X_CONTENT_TYPE_OPTIONS, "nosniff".parse().unwrap());
+        self.default_headers
+            .insert(X_FRAME_OPTIONS, "sameorigin".parse().unwrap());
+        self.default_headers
+            .insert(SERVER, "cloudflare".parse().unwrap());
+        self.default_headers.insert(USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36".parse().unwrap());
 
-        if self.current_bet > self.balance && self.use_site_balance {
-            println!("[FAIL] Resetting {:0>.8}", self.site_balance);
-            self.balance = self.site_balance * self.balance_modifier;
-            self.initial_balance = self.site_balance * self.balance_modifier;
-            self.wins = 0;
-            self.losses = 0;
-            self.seed_profit = 0.;
-            self.strategy.reset();
-            let randomize_url = Url::parse_with_params(
-                "https://duckdice.io/api/randomize",
-                &[("api_key", API_KEY)],
-            )
-            .expect("Failed to parse randomize URL");
-
-            self.client_seed = rand::rng()
-                .sample_iter(rand::distr::Alphabetic)
-                .take(30)
-                .map(char::from)
-                .collect();
-            let res_randomize = self
-                .client
-                .post(randomize_url)
-                .json(&json!({
-                    "clientSeed": self.client_seed.clone(),
-                }))
-                .send()
-                .await?;
-
-            if let Some(retry_after) = res_randomize.headers().get("retry-after") {
-                let _ = tokio::time::sleep(Duration::from_secs(
-                    retry_after.to_str().unwrap().parse::<u64>().unwrap(),
-                ))
-                .await;
-            }
-            self.initialized_hash = false;
-            let user_info_url = Url::parse(&format!(
-                "https://duckdice.io/api/bot/user-info?api_key={API_KEY}",
-            ))
-            .expect("Failed to parse user_info URL");
-            let res: serde_json::Value =
-                self.client.get(user_info_url).send().await?.json().await?;
-            let res: UserInfoJson = serde_json::from_value(res).unwrap();
-            let res: UserInfo = res.into();
-
-            for balance in &res.balances {
-                if balance.currency == self.currency.to_string().as_str() {
-                    if let Some(main) = &if self.faucet {
-                        balance.faucet.clone()
-                    } else {
-                        balance.main.clone()
-                    } {
-                        let _val = main.parse::<f32>().unwrap_or(0.);
-                        self.strategy
-                            .set_balance(self.site_balance * self.balance_modifier);
-                        self.balance = self.site_balance * self.balance_modifier;
-                        self.initial_balance = self.site_balance * self.balance_modifier;
-                        self.strategy.reset();
-                    }
-                }
-            }
+        self.build_client()
+    }
 
-            let next_bet_data = self.strategy.get_next_bet(prediction, confidence);
-            self.current_bet = next_bet_data.0;
-            self.chance = next_bet_data.2;
-            let _high = next_bet_data.3;
-        }
+    async fn place_bet(&mut self, wager: &BetDecision) -> Result<BetOutcome, BetError> {
+        let is_high = wager.kind.high(Sites::DuckDiceIo)?;
+        let bet_url = Url::parse_with_params(
+            &format!("{}/play", self.base_url),
+            &[("api_key", self.api_key.expose())],
+        )
+        .expect("Failed to parse do_bet URL");
 
         let res = self
-            .client
-            .post(bet_url)
-            .json(&BetMake {
+            .requester
+            .send_bet(self.client.post(bet_url).json(&BetMake {
                 symbol: self.currency.to_string(),
-                chance: format!("{:.2}", self.chance).parse::<f32>().unwrap_or(0.),
-                is_high: high,
-                amount: format!("{:.8}", self.current_bet)
-                    .parse::<f32>()
-                    .unwrap_or(0.),
+                chance: format!("{:.2}", wager.chance).parse::<f32>().unwrap_or(0.),
+                is_high,
+                amount: format!("{:.8}", wager.amount).parse::<f32>().unwrap_or(0.),
                 user_wagering_bonus_hash: None, /*Some("97a8d827da".to_string()),*/
                 faucet: if self.faucet { Some(true) } else { None },
                 tle_hash: if self.faucet {
@@ -726,126 +532,196 @@ impl Site for DuckDiceIo {
                 } else {
                     self.tle_hash.clone()
                 },
-            })
-            .send()
+            }))
             .await;
 
-        match res {
-            Ok(res) => {
-                if res.status() == 403 {
-                    self.rolls -= 1;
-                    self.default_headers.remove("cf-ray");
-                    self.default_headers
-                        .insert("cf-ray", res.headers()["cf-ray"].clone());
-
-                    self.client = reqwest::Client::builder()
-                        .default_headers(self.default_headers.clone())
-                        .user_agent("DuckDiceBot/1.0.0")
-                        .redirect(Policy::limited(200))
-                        .build()?;
-                    return Err(BetError::EmptyReply);
-                }
-                let res: serde_json::Value = res.json().await?;
-
-                let res: BetMakeResponseJson = serde_json::from_value(res).unwrap();
-                let mut res: BetMakeResponse = res.into();
-
-                if !self.initialized_hash {
-                    let res_bet_data = self
-                        .client
-                        .get(format!("https://duckdice.io/api/bet/{}", res.bet.hash))
-                        .send()
-                        .await?;
-                    let res_bet_data: serde_json::Value = res_bet_data.json().await?;
-
-                    res.bet.previous_hash = self.previous_hash.clone();
-                    self.previous_hash = res_bet_data["seed"]["serverSeedHash"]
-                        .as_str()
-                        .unwrap()
-                        .to_string();
-                    self.client_seed = res_bet_data["seed"]["clientSeed"]
-                        .as_str()
-                        .unwrap()
-                        .to_string();
-                    res.bet.hash = self.previous_hash.clone();
-                    self.initialized_hash = true;
-                } else {
-                    res.bet.previous_hash = self.previous_hash.clone();
-                    res.bet.hash = self.previous_hash.clone();
-                }
-
-                self.default_headers.remove(AUTHORIZATION);
-                self.client = reqwest::Client::builder()
-                    .default_headers(self.default_headers.clone())
-                    .user_agent("DuckDiceBot/1.0.0")
-                    .redirect(Policy::limited(200))
-                    .build()?;
-
-                self.history.push(res.clone().into());
-                if self.history.len() > self.history_size {
-                    self.history = self.history[1..].to_vec();
-                }
-
-                let mut bet_result: BetResult = res.into();
-                bet_result.client_seed = self.client_seed.clone();
-
-                Ok(bet_result)
+        let res = match res {
+            // Cloudflare turned the bet away, it's skipped on a fresh client.
+            Err(BetError::HttpStatus(StatusCode::FORBIDDEN)) => {
+                self.build_client()?;
+
+                return Err(BetError::EmptyReply);
             }
-            Err(_) => Err(BetError::EmptyReply),
+            res => res?,
+        };
+        let res: BetMakeResponseJson = read_json(res).await?;
+        let mut res: BetMakeResponse = res.into();
+
+        if !self.initialized_hash {
+            let seed = self.fetch_bet_seed(&res.bet.hash).await?;
+            let seed_field = |key: &str| {
+                seed[key]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| BetError::Parse {
+                        raw: seed.to_string(),
+                    })
+            };
+
+            let server_seed_hash = seed_field("serverSeedHash")?;
+            self.client_seed = seed_field("clientSeed")?;
+            if server_seed_hash != self.server_seed_hash {
+                self.previous_hash =
+                    std::mem::replace(&mut self.server_seed_hash, server_seed_hash);
+            }
+            self.bets
+                .insert(self.server_seed_hash.clone(), res.bet.hash.clone());
+            self.initialized_hash = true;
         }
-    }
+        // The reply's hash is the bet's own, outcomes carry the pairs' hashes instead.
+        res.bet.previous_hash = self.previous_hash.clone();
+        res.bet.hash = self.server_seed_hash.clone();
+        self.nonce = res.bet.nonce + 1;
+
+        self.default_headers.remove(AUTHORIZATION);
+        self.build_client()?;
 
-    fn on_win(&mut self, bet_result: &BetResult) {
-        self.offline_balance += bet_result.win_amount;
-        self.balance += bet_result.win_amount;
-        self.profit += bet_result.win_amount;
-        self.seed_profit += bet_result.win_amount;
-        self.strategy.on_win(bet_result);
-        self.wins += 1;
+        let mut bet_result: BetOutcome = res.into();
+        bet_result.client_seed = self.client_seed.clone();
+
+        Ok(bet_result.complete(wager, Sites::DuckDiceIo))
     }
 
-    fn on_lose(&mut self, bet_result: &BetResult) {
-        self.offline_balance += bet_result.win_amount;
-        self.balance += bet_result.win_amount;
-        self.profit += bet_result.win_amount;
-        self.seed_profit += bet_result.win_amount;
-        self.losses += 1;
-        let mut bet_result = bet_result.clone();
-        bet_result.win_amount = -bet_result.win_amount;
-        self.strategy.on_lose(&bet_result);
+    async fn fetch_balances(&mut self) -> Result<Vec<CurrencyBalance>, BetError> {
+        let user_info_url = Url::parse_with_params(
+            &format!("{}/bot/user-info", self.base_url),
+            &[("api_key", self.api_key.expose())],
+        )
+        .expect("Failed to parse user_info URL");
+
+        let res = self.requester.send(self.client.get(user_info_url)).await?;
+        let res: UserInfoJson = read_json(res).await?;
+        let res: UserInfo = res.into();
+
+        let balances = res
+            .balances
+            .into_iter()
+            .filter_map(|balance| {
+                let amount = if self.faucet {
+                    balance.faucet
+                } else {
+                    balance.main
+                }?;
+
+                Some(CurrencyBalance {
+                    symbol: balance.currency,
+                    amount: amount.parse::<f32>().unwrap_or(0.),
+                })
+            })
+            .collect();
+
+        Ok(balances)
     }
 
-    fn get_history(&self) -> Vec<BetResult> {
-        self.history.clone()
+    async fn rotate_seed(&mut self) -> Result<(), BetError> {
+        let randomize_url = Url::parse_with_params(
+            &format!("{}/randomize", self.base_url),
+            &[("api_key", self.api_key.expose())],
+        )
+        .expect("Failed to parse randomize URL");
+
+        self.client_seed = random_seed(30);
+
+        self.requester
+            .send(self.client.post(randomize_url).json(&json!({
+                "clientSeed": self.client_seed.clone(),
+            })))
+            .await?;
+
+        self.initialized_hash = false;
+        self.nonce = 0;
+
+        Ok(())
     }
 
-    fn get_rolls(&self) -> u64 {
-        self.rolls
+    fn seed_info(&self) -> SeedInfo {
+        SeedInfo {
+            server_seed_hash: self.server_seed_hash.clone(),
+            client_seed: self.client_seed.clone(),
+            nonce: self.nonce,
+        }
     }
 
-    fn get_current_bet(&self) -> f32 {
-        self.current_bet
+    async fn fetch_revealed_seed(&mut self, server_seed_hash: &str) -> Result<String, BetError> {
+        if let Some(server_seed) = self.revealed.get(server_seed_hash) {
+            return Ok(server_seed.to_string());
+        }
+
+        let unavailable = || BetError::SeedUnavailable {
+            server_seed_hash: server_seed_hash.to_string(),
+        };
+        let bet_hash = self.bets.get(server_seed_hash).ok_or_else(unavailable)?;
+        // The active pair's bets come back with a null `serverSeed`.
+        let server_seed = self.fetch_bet_seed(bet_hash).await?["serverSeed"]
+            .as_str()
+            .filter(|server_seed| !server_seed.is_empty())
+            .map(str::to_string)
+            .ok_or_else(unavailable)?;
+        self.revealed
+            .insert(server_seed_hash.to_string(), server_seed.clone());
+
+        Ok(server_seed)
     }
 
-    fn get_current_multiplier(&self) -> f32 {
-        1. / (self.chance / 100.)
+    /// One account holds every currency and they share the seed pair.
+    async fn switch_currency(&mut self, currency: &Currency) -> Result<(), BetError> {
+        self.currency = currency.clone();
+
+        Ok(())
     }
 
-    fn get_history_size(&self) -> usize {
-        self.history_size
+    async fn fetch_bets(&mut self, bet_ids: &[String]) -> Result<Option<Vec<SiteBet>>, BetError> {
+        let mut site_bets = Vec::new();
+        for bet_id in bet_ids {
+            let mut res = match self.fetch_bet(bet_id).await {
+                Err(BetError::HttpStatus(StatusCode::NOT_FOUND)) => continue,
+                res => res?,
+            };
+            let bet: Bet = serde_json::from_value::<BetJson>(res["bet"].take())
+                .map_err(|_| BetError::Parse {
+                    raw: res.to_string(),
+                })?
+                .into();
+            site_bets.push(SiteBet {
+                bet_id: bet.id,
+                amount: bet.bet_amount,
+                profit: bet.profit,
+            });
+        }
+
+        Ok(Some(site_bets))
     }
 
-    fn get_profit(&self) -> f32 {
-        self.profit
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(DuckDiceState {
+            server_seed_hash: self.server_seed_hash.clone(),
+            previous_hash: self.previous_hash.clone(),
+            client_seed: self.client_seed.clone(),
+            nonce: self.nonce,
+            initialized_hash: self.initialized_hash,
+            bets: self.bets.clone(),
+            revealed: self.revealed.clone(),
+        })
+        .unwrap_or_default()
     }
 
-    fn get_balance(&self) -> f32 {
-        self.balance
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        let state: DuckDiceState = serde_json::from_value(state)?;
+
+        self.server_seed_hash = state.server_seed_hash;
+        self.previous_hash = state.previous_hash;
+        self.client_seed = state.client_seed;
+        self.nonce = state.nonce;
+        self.initialized_hash = state.initialized_hash;
+        self.bets = state.bets;
+        self.revealed = state.revealed;
+
+        Ok(())
     }
 }
 
 impl SiteConfig for DuckDiceIo {
-    fn with_api_key(mut self, api_key: String) -> Self
+    fn with_api_key(mut self, api_key: Secret) -> Self
     where
         Self: Sized,
     {
@@ -862,22 +738,4 @@ impl SiteConfig for DuckDiceIo {
 
         self
     }
-
-    fn with_strategy(mut self, strategy: TomlStrategies) -> Self
-    where
-        Self: Sized,
-    {
-        self.strategy = match strategy {
-            TomlStrategies::AiFight => Box::new(crate::strategies::ai_fight::AIFight::default()),
-            TomlStrategies::MyStrategy => {
-                Box::new(crate::strategies::my_strategy::MyStrat::default())
-            }
-            TomlStrategies::BlaksRunner => {
-                Box::new(crate::strategies::blaks_runner::BlaksRunner5_0::default())
-            }
-            TomlStrategies::None => Box::new(crate::strategies::none::NoStrat::default()),
-        };
-
-        self
-    }
 }
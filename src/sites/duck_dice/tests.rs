@@ -0,0 +1,377 @@
+use super::mock_server::{Balance, Fault, MockDuckDice, API_KEY};
+use super::*;
+use crate::sites::ledger::Ledger;
+use crate::sites::provably_fair::{DuckDiceRoll, RollAlgorithm};
+use crate::sites::seed_chain::{ChainIssue, SeedChain};
+use crate::sites::session::BettingSession;
+use crate::sites::Site;
+use crate::sites::{BetKind, MAX_ROLL};
+
+async fn mock() -> MockDuckDice {
+    MockDuckDice::start(&[
+        (
+            "ETH",
+            Balance {
+                main: 0.,
+                faucet: 0.01,
+            },
+        ),
+        (
+            "BTC",
+            Balance {
+                main: 0.5,
+                faucet: 0.,
+            },
+        ),
+    ])
+    .await
+}
+
+fn duck_dice(mock: &MockDuckDice) -> DuckDiceIo {
+    DuckDiceIo::default()
+        .with_base_url(mock.base_url())
+        .with_requester(Requester::new(Sites::DuckDiceIo).with_rate(1000.))
+        .with_api_key(Secret::new(API_KEY.to_string()))
+        .with_currency(Currency::ETH)
+}
+
+fn wager(high: bool) -> BetDecision {
+    BetDecision {
+        amount: 0.0001,
+        chance: 49.5,
+        multiplier: 2.,
+        kind: BetKind::from_high(high),
+    }
+}
+
+#[tokio::test]
+async fn login_reads_per_currency_balances() {
+    let mock = mock().await;
+    let mut duck_dice = duck_dice(&mock);
+    duck_dice.login().await.unwrap();
+
+    let balances = duck_dice.fetch_balances().await.unwrap();
+    let amount = |symbol: &str| {
+        balances
+            .iter()
+            .find(|balance| balance.symbol == symbol)
+            .map(|balance| balance.amount)
+    };
+    assert_eq!(amount("ETH"), Some(0.01));
+    assert_eq!(amount("BTC"), Some(0.));
+}
+
+#[tokio::test]
+async fn bets_settle_with_provably_fair_rolls() {
+    let mock = mock().await;
+    let mut duck_dice = duck_dice(&mock);
+    duck_dice.login().await.unwrap();
+
+    let mut results = Vec::new();
+    for high in [true, false, true] {
+        results.push(duck_dice.place_bet(&wager(high)).await.unwrap());
+    }
+    let profit = results
+        .iter()
+        .map(|bet_result| bet_result.win_amount as f64)
+        .sum::<f64>();
+    assert!((mock.balance("ETH").faucet - (0.01 + profit)).abs() < 1e-7);
+
+    let seed = duck_dice.seed_info();
+    assert_eq!(seed.client_seed, "mock-client-seed");
+    assert_eq!(seed.server_seed_hash, mock.seeds().server_seed_hash);
+    assert_eq!(seed.nonce, 3);
+    assert!(matches!(
+        duck_dice.fetch_revealed_seed(&seed.server_seed_hash).await,
+        Err(BetError::SeedUnavailable { .. })
+    ));
+
+    duck_dice.rotate_seed().await.unwrap();
+    assert_eq!(duck_dice.seed_info().client_seed, mock.seeds().client_seed);
+    let server_seed = duck_dice
+        .fetch_revealed_seed(&seed.server_seed_hash)
+        .await
+        .unwrap();
+    for (bet_result, high) in results.iter().zip([true, false, true]) {
+        assert_eq!(bet_result.client_seed, "mock-client-seed");
+        assert_eq!(bet_result.kind, BetKind::from_high(high));
+        assert_eq!(
+            bet_result.threshold,
+            wager(high).threshold(Sites::DuckDiceIo)
+        );
+        assert_eq!(bet_result.bet_amount, 0.0001);
+        assert_eq!(
+            bet_result.result,
+            bet_result.kind.wins(bet_result.number, 49.5, MAX_ROLL)
+        );
+        assert!(bet_result.timestamp > 0);
+        assert_eq!(
+            bet_result.number,
+            DuckDiceRoll.roll(
+                &server_seed,
+                &bet_result.client_seed,
+                bet_result.nonce as u64
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn ranges_are_rejected_before_reaching_the_site() {
+    let mock = mock().await;
+    let mut duck_dice = duck_dice(&mock);
+    duck_dice.login().await.unwrap();
+
+    let err = duck_dice
+        .place_bet(&BetDecision {
+            kind: BetKind::InRange(0, 99),
+            ..wager(true)
+        })
+        .await;
+    assert!(matches!(
+        err,
+        Err(BetError::UnsupportedBetKind {
+            site: Sites::DuckDiceIo,
+            kind: BetKind::InRange(0, 99),
+        })
+    ));
+    assert_eq!(mock.balance("ETH").faucet, 0.01);
+}
+
+#[tokio::test]
+async fn rate_limits_are_retried_transparently() {
+    let mock = mock().await;
+    let mut duck_dice = duck_dice(&mock);
+    duck_dice.login().await.unwrap();
+
+    mock.fail_next(Fault::RateLimited { retry_after: 0 }, 2);
+    assert!(duck_dice.fetch_balances().await.is_ok());
+    assert_eq!(mock.server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn failures_are_typed() {
+    let mock = mock().await;
+    let mut duck_dice = duck_dice(&mock);
+    duck_dice.login().await.unwrap();
+
+    mock.fail_next(Fault::Forbidden, 1);
+    assert!(matches!(
+        duck_dice.place_bet(&wager(true)).await,
+        Err(BetError::EmptyReply)
+    ));
+    assert_eq!(mock.balance("ETH").faucet, 0.01);
+
+    mock.fail_next(Fault::MalformedJson, 1);
+    assert!(matches!(
+        duck_dice.place_bet(&wager(true)).await,
+        Err(BetError::Parse { .. })
+    ));
+
+    let mut wager = wager(true);
+    wager.amount = 1.;
+    assert!(matches!(
+        duck_dice.place_bet(&wager).await,
+        Err(BetError::BetRejected { .. })
+    ));
+
+    let mut duck_dice = self::duck_dice(&mock).with_api_key(Secret::new("wrong".to_string()));
+    duck_dice.login().await.unwrap();
+    assert!(matches!(
+        duck_dice.fetch_balances().await,
+        Err(BetError::AuthExpired)
+    ));
+}
+
+#[tokio::test]
+async fn session_tracks_balance_over_the_mock() {
+    let mock = mock().await;
+    let mut session = BettingSession::new(
+        Box::new(duck_dice(&mock)),
+        crate::strategies::from_toml(crate::config::TomlStrategies::None, 1e-8),
+    );
+    session.login().await.unwrap();
+    assert_eq!(session.get_balance(), 0.01);
+
+    for _ in 0..5 {
+        let bet_result = session.do_bet(5000., 50.).await.unwrap();
+        if bet_result.result {
+            session.on_win(&bet_result);
+        } else {
+            session.on_lose(&bet_result);
+        }
+    }
+    assert_eq!(session.get_rolls(), 5);
+    assert!((session.get_balance() as f64 - mock.balance("ETH").faucet).abs() < 1e-7);
+}
+
+#[tokio::test]
+async fn reconcile_reports_drift_and_unknown_bets() {
+    let mock = mock().await;
+    let mut session = BettingSession::new(
+        Box::new(duck_dice(&mock)),
+        crate::strategies::from_toml(crate::config::TomlStrategies::None, 1e-8),
+    );
+    session.login().await.unwrap();
+
+    let mut bet_ids = Vec::new();
+    for _ in 0..3 {
+        bet_ids.push(session.do_bet(5000., 50.).await.unwrap().bet_id);
+    }
+    let report = session.reconcile().await.unwrap();
+    assert_eq!(report.bets, 3);
+    assert!(report.drift().abs() < 1e-7, "{report}");
+    assert!(report.is_clean(), "{report}");
+
+    session.do_bet(5000., 50.).await.unwrap();
+    let forgotten = session.do_bet(5000., 50.).await.unwrap().bet_id;
+    mock.forget_bet(&forgotten);
+    mock.adjust_balance("ETH", -0.001);
+    let report = session.reconcile().await.unwrap();
+    assert_eq!(report.bets, 2);
+    assert!((report.drift() + 0.001).abs() < 1e-7, "{report}");
+    assert_eq!(report.unknown, vec![forgotten]);
+    assert!(report.mismatched.is_empty() && report.duplicates.is_empty());
+    assert_eq!(report.missing, 0);
+    assert!(!bet_ids.iter().any(String::is_empty));
+}
+
+async fn settle(session: &mut BettingSession) -> BetOutcome {
+    let bet_result = session.do_bet(5000., 50.).await.unwrap();
+    if bet_result.result {
+        session.on_win(&bet_result);
+    } else {
+        session.on_lose(&bet_result);
+    }
+
+    bet_result
+}
+
+#[tokio::test]
+async fn expired_sessions_log_in_again_mid_run() {
+    let mock = mock().await;
+    let mut session = BettingSession::new(
+        Box::new(duck_dice(&mock)),
+        crate::strategies::from_toml(crate::config::TomlStrategies::BlaksRunner, 1e-8),
+    );
+    session.login().await.unwrap();
+    for _ in 0..5 {
+        settle(&mut session).await;
+    }
+
+    mock.fail_next(Fault::Unauthenticated, 1);
+    assert!(matches!(
+        session.do_bet(5000., 50.).await,
+        Err(BetError::AuthExpired)
+    ));
+    let before = session.save_state();
+    session.reauthenticate().await.unwrap();
+    let after = session.save_state();
+    assert_eq!(after["strategy"], before["strategy"]);
+    assert_eq!(after["initial_balance"], before["initial_balance"]);
+    assert_eq!(after["balance"], before["balance"]);
+    assert_eq!(session.get_rolls(), 5);
+
+    settle(&mut session).await;
+    assert_eq!(session.get_rolls(), 6);
+}
+
+#[tokio::test]
+async fn switching_currencies_keeps_separate_books() {
+    let mock = mock().await;
+    mock.adjust_balance("BTC", 0.02);
+    let mut session = BettingSession::new(
+        Box::new(duck_dice(&mock)),
+        crate::strategies::from_toml(crate::config::TomlStrategies::None, 1e-8),
+    );
+    session.login().await.unwrap();
+
+    let eth_profit = settle(&mut session).await.win_amount + settle(&mut session).await.win_amount;
+
+    session.switch_currency(&Currency::BTC).await.unwrap();
+    assert_eq!(session.get_symbol(), "BTC");
+    assert_eq!(session.get_balance(), 0.02);
+    assert_eq!(session.get_profit(), 0.);
+    assert!(session.get_history().is_empty());
+    let btc_bet = settle(&mut session).await;
+    assert_eq!(btc_bet.symbol, "BTC");
+    let report = session.reconcile().await.unwrap();
+    assert_eq!((report.symbol.as_str(), report.bets), ("BTC", 1));
+    assert!(report.drift().abs() < 1e-7 && report.is_clean(), "{report}");
+
+    let balances = session.list_balances().await.unwrap();
+    assert_eq!(balances.len(), 2);
+    session.switch_currency(&Currency::ETH).await.unwrap();
+    assert_eq!(session.get_history().len(), 2);
+    assert!((session.get_profit() - eth_profit).abs() < 1e-9);
+    assert_eq!(
+        session.get_currency_profits(),
+        vec![
+            ("ETH".to_string(), session.get_profit()),
+            ("BTC".to_string(), btc_bet.win_amount)
+        ]
+    );
+
+    let state = session.save_state();
+    let switches = state["ledger"]["switches"].as_array().unwrap();
+    assert_eq!(switches.len(), 2);
+    assert_eq!(switches[0]["from"], "ETH");
+    assert_eq!(switches[0]["to"], "BTC");
+    assert_eq!(switches[0]["bets"], 2);
+}
+
+fn recorded_audits(session: &BettingSession) -> Vec<serde_json::Value> {
+    session.save_state()["ledger"]["audits"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn retired_pairs_are_audited() {
+    let mock = mock().await;
+    let mut session = BettingSession::new(
+        Box::new(duck_dice(&mock)),
+        crate::strategies::from_toml(crate::config::TomlStrategies::None, 1e-8),
+    );
+    session.login().await.unwrap();
+    // Audits run in the background and are collected on the next bet.
+    let settle_audits = || tokio::time::sleep(std::time::Duration::from_millis(50));
+
+    for _ in 0..3 {
+        settle(&mut session).await;
+    }
+    let retired = session.rotate_seed().await.unwrap();
+    settle_audits().await;
+    settle(&mut session).await;
+    let audits = recorded_audits(&session);
+    assert_eq!(audits.len(), 1);
+    assert_eq!(audits[0]["server_seed_hash"], retired.server_seed_hash);
+    assert_eq!(audits[0]["bets"], 3);
+    assert_eq!(audits[0]["passed"], 3);
+
+    session.rotate_seed().await.unwrap();
+    // Shutting down waits for the audits still running.
+    session.finish_audits().await.unwrap();
+    assert_eq!(recorded_audits(&session).len(), 2);
+
+    settle(&mut session).await;
+    mock.reveal_other_seeds();
+    session.rotate_seed().await.unwrap();
+    settle_audits().await;
+    assert!(matches!(
+        session.do_bet(5000., 50.).await,
+        Err(BetError::UnfairRolls { failed: 1, .. })
+    ));
+    let audits = recorded_audits(&session);
+    assert_eq!(audits.len(), 3);
+    assert_eq!(audits[2]["failures"].as_array().map(Vec::len), Some(1));
+
+    let ledger: Ledger = serde_json::from_value(session.save_state()["ledger"].clone()).unwrap();
+    let chain = SeedChain::build(Sites::DuckDiceIo, ledger.bets(), ledger.rotations());
+    assert_eq!(chain.pairs.len(), 3);
+    assert!(matches!(
+        chain.issues[..],
+        [ChainIssue::RevealMismatch { .. }]
+    ));
+}
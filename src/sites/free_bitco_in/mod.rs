@@ -0,0 +1,301 @@
+use async_trait::async_trait;
+use rand::Rng;
+use reqwest::{cookie::Jar, Url};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{
+    config::SiteConfig,
+    credentials::Secret,
+    sites::{
+        random_seed, read_json, request::Requester, BetDecision, BetError, BetOutcome,
+        CurrencyBalance, SeedInfo, SeedMap, SiteCapabilities, SiteClient, Sites,
+    },
+};
+
+mod protocol;
+#[cfg(test)]
+mod tests;
+
+pub use protocol::{to_btc, BetSiteResult, LoginReply, UserStats};
+
+#[derive(Debug)]
+pub struct LoginRequest {
+    pub csrf_token: String,
+    pub op: String,
+    pub btc_address: String,
+    pub password: Secret,
+    pub tfa_code: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct FreeBitcoInState {
+    client_seed: String,
+    server_seed_hash: String,
+    nonce: u64,
+    #[serde(default)]
+    revealed: SeedMap,
+}
+
+pub struct FreeBitcoIn {
+    pub client_seed: String,
+    pub user_stats: UserStats,
+    server_seed_hash: String,
+    nonce: u64,
+    /// Every bet reveals the server seed of the roll before it.
+    revealed: SeedMap,
+    client: reqwest::Client,
+    requester: Requester,
+    cookie_jar: Arc<Jar>,
+    csrf_token: String,
+    btc_address: String,
+    password: Secret,
+    /// The max bet comes back with every bet.
+    capabilities: SiteCapabilities,
+}
+
+impl Default for FreeBitcoIn {
+    fn default() -> Self {
+        Self {
+            client_seed: random_seed(30),
+            user_stats: UserStats::default(),
+            server_seed_hash: String::new(),
+            nonce: 0,
+            revealed: SeedMap::default(),
+            client: reqwest::Client::new(),
+            requester: Requester::new(Sites::FreeBitcoIn),
+            cookie_jar: Arc::new(Jar::default()),
+            csrf_token: String::new(),
+            btc_address: String::new(),
+            password: Secret::default(),
+            capabilities: SiteCapabilities {
+                house_edge: Sites::FreeBitcoIn.house_edge(),
+                ..SiteCapabilities::default()
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl SiteClient for FreeBitcoIn {
+    fn site(&self) -> Sites {
+        Sites::FreeBitcoIn
+    }
+
+    fn symbol(&self) -> String {
+        "BTC".to_string()
+    }
+
+    fn capabilities(&self) -> SiteCapabilities {
+        self.capabilities.clone()
+    }
+
+    /// Bets are placed by multiplier, the chance follows it.
+    fn odds(&self, _chance: f32, multiplier: f32) -> (f32, f32) {
+        let multiplier = self.capabilities.clamp_multiplier(multiplier);
+
+        (self.capabilities.chance(multiplier), multiplier)
+    }
+
+    async fn login(&mut self) -> Result<(), BetError> {
+        self.client = reqwest::Client::builder()
+            .cookie_store(true)
+            .cookie_provider(Arc::clone(&self.cookie_jar))
+            .build()?;
+
+        let url = "https://freebitco.in/"
+            .parse::<reqwest::Url>()
+            .expect("Failed to parse freebitco.in address");
+        let charset = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789"
+            .chars()
+            .collect::<Vec<char>>();
+        let mut csrf_token = String::new();
+        {
+            let mut rng = rand::rng();
+            for _ in 0..12 {
+                let rand_num = rng.random_range(0..charset.len());
+                csrf_token.push(charset[rand_num]);
+            }
+        }
+        self.csrf_token = csrf_token.clone();
+
+        self.cookie_jar
+            .add_cookie_str(&format!("csrf_token={csrf_token}; Path=/; Secure"), &url);
+
+        let _ = self.requester.send(self.client.get(url.clone())).await?;
+        let login_post = LoginRequest {
+            csrf_token: "".to_string(),
+            op: "login_new".to_string(),
+            btc_address: self.btc_address.clone(),
+            password: self.password.clone(),
+            tfa_code: "".to_string(),
+        };
+
+        let login_response = self
+            .requester
+            .send(self.client.post(url.clone()).form(&[
+                ("csrf_token", login_post.csrf_token),
+                ("op", login_post.op),
+                ("btc_address", login_post.btc_address),
+                ("password", login_post.password.expose().to_string()),
+                ("tfa_code", login_post.tfa_code),
+            ]))
+            .await?
+            .text()
+            .await?;
+
+        let login = LoginReply::try_from(login_response.as_str())?;
+
+        self.cookie_jar.add_cookie_str(
+            &format!("btc_address={}; Path=/; Secure", login.btc_address),
+            &url,
+        );
+        self.cookie_jar.add_cookie_str(
+            &format!("fbtc_session={}; Path=/; Secure", login.session.expose()),
+            &url,
+        );
+        self.cookie_jar.add_cookie_str(
+            &format!("fbtc_userid={}; Path=/; Secure", login.user_id),
+            &url,
+        );
+        self.cookie_jar
+            .add_cookie_str("have_account=1; Path=/; Secure", &url);
+        self.cookie_jar.add_cookie_str(
+            &format!("password={}; Path=/; Secure", login.password.expose()),
+            &url,
+        );
+
+        Ok(())
+    }
+
+    async fn place_bet(&mut self, wager: &BetDecision) -> Result<BetOutcome, BetError> {
+        let high = wager.kind.high(Sites::FreeBitcoIn)?;
+        let bet_url = Url::parse_with_params(
+            "https://freebitco.in/cgi-bin/bet.pl",
+            &[
+                ("m", if high { "hi" } else { "lo" }),
+                ("client_seed", &self.client_seed),
+                ("jackpot", "0"),
+                ("stake", &format!("{:.8}", wager.amount)),
+                ("multiplier", &format!("{:.2}", wager.multiplier)),
+                ("csrf_token", &self.csrf_token.clone()),
+                ("rand", {
+                    let mut rng = rand::rng();
+
+                    &format!("{}", rng.random::<f64>())
+                }),
+            ],
+        )
+        .expect("Failed to create freebitco.in bet URL");
+
+        let bet_response = self
+            .requester
+            .send_bet(self.client.get(bet_url))
+            .await?
+            .text()
+            .await?;
+        let bet_result = match BetSiteResult::try_from(bet_response.as_str()) {
+            Ok(bet_result) => bet_result,
+            Err(BetError::InsufficientBalance { .. }) => {
+                return Err(BetError::InsufficientBalance {
+                    needed: wager.amount,
+                    available: to_btc(self.user_stats.balance),
+                })
+            }
+            Err(err) => return Err(err),
+        };
+
+        self.server_seed_hash = bet_result.server_seed_hash_next_roll.clone();
+        self.nonce = bet_result.nonce_next_roll;
+        self.capabilities.max_bet =
+            Some(to_btc(bet_result.max_bet)).filter(|max_bet| *max_bet > 0.);
+        self.revealed.insert(
+            bet_result.server_seed_hash_previous_roll.clone(),
+            bet_result.server_seed_previous_roll.clone(),
+        );
+
+        Ok(BetOutcome::from(bet_result).complete(wager, Sites::FreeBitcoIn))
+    }
+
+    async fn fetch_balances(&mut self) -> Result<Vec<CurrencyBalance>, BetError> {
+        let user_stats_res = self
+            .requester
+            .send(
+                self.client
+                    .get("https://freebitco.in/cgi-bin/api.pl?op=get_user_stats"),
+            )
+            .await?;
+        let user_stats_res: serde_json::Value = read_json(user_stats_res).await?;
+        self.user_stats = UserStats::try_from(user_stats_res)?;
+
+        Ok(vec![CurrencyBalance {
+            symbol: self.symbol(),
+            amount: protocol::to_btc(self.user_stats.balance),
+        }])
+    }
+
+    /// The client seed is sent with every bet, picking a new one starts a new pair.
+    async fn rotate_seed(&mut self) -> Result<(), BetError> {
+        self.client_seed = random_seed(30);
+
+        Ok(())
+    }
+
+    fn seed_info(&self) -> SeedInfo {
+        SeedInfo {
+            server_seed_hash: self.server_seed_hash.clone(),
+            client_seed: self.client_seed.clone(),
+            nonce: self.nonce,
+        }
+    }
+
+    async fn fetch_revealed_seed(&mut self, server_seed_hash: &str) -> Result<String, BetError> {
+        self.revealed
+            .get(server_seed_hash)
+            .map(str::to_string)
+            .ok_or_else(|| BetError::SeedUnavailable {
+                server_seed_hash: server_seed_hash.to_string(),
+            })
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(FreeBitcoInState {
+            client_seed: self.client_seed.clone(),
+            server_seed_hash: self.server_seed_hash.clone(),
+            nonce: self.nonce,
+            revealed: self.revealed.clone(),
+        })
+        .unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        let state: FreeBitcoInState = serde_json::from_value(state)?;
+
+        self.client_seed = state.client_seed;
+        self.server_seed_hash = state.server_seed_hash;
+        self.nonce = state.nonce;
+        self.revealed = state.revealed;
+
+        Ok(())
+    }
+}
+
+impl SiteConfig for FreeBitcoIn {
+    fn with_username(mut self, username: String) -> Self
+    where
+        Self: Sized,
+    {
+        self.btc_address = username;
+
+        self
+    }
+
+    fn with_password(mut self, password: Secret) -> Self
+    where
+        Self: Sized,
+    {
+        self.password = password;
+
+        self
+    }
+}
@@ -0,0 +1,298 @@
+//! freebitco.in's colon-delimited replies. Amounts are integer satoshis, parsed from the
+//! decimal strings the site sends so no float rounding creeps in.
+
+use crate::credentials::Secret;
+use crate::sites::BetError;
+
+pub const SATOSHIS_PER_BTC: i64 = 100_000_000;
+
+/// Field positions of a successful `bet.pl` reply.
+mod bet_field {
+    pub const SUCCESS_CODE: usize = 0;
+    pub const RESULT: usize = 1;
+    pub const ROLLED_NUMBER: usize = 2;
+    pub const USER_BALANCE: usize = 3;
+    pub const AMOUNT_WON: usize = 4;
+    pub const SERVER_SEED_HASH_NEXT_ROLL: usize = 6;
+    pub const CLIENT_SEED_PREVIOUS_ROLL: usize = 7;
+    pub const NONCE_NEXT_ROLL: usize = 8;
+    pub const SERVER_SEED_PREVIOUS_ROLL: usize = 9;
+    pub const SERVER_SEED_HASH_PREVIOUS_ROLL: usize = 10;
+    pub const PREVIOUS_NONCE: usize = 12;
+    pub const JACKPOT_RESULT: usize = 13;
+    pub const JACKPOT_AMOUNT_WON: usize = 15;
+    pub const BONUS_ACCOUNT_BALANCE_AFTER_BET: usize = 16;
+    pub const BONUS_ACCOUNT_WAGER_REMAINING: usize = 17;
+    pub const MAX_AMOUNT_BONUS_ELIGIBLE: usize = 18;
+    pub const MAX_BET: usize = 19;
+    pub const ACCOUNT_BALANCE_BEFORE_BET: usize = 20;
+    pub const ACCOUNT_BALANCE_AFTER_BET: usize = 21;
+    pub const BONUS_ACCOUNT_BALANCE_BEFORE_BET: usize = 22;
+
+    /// Fields a reply has to carry.
+    pub const COUNT: usize = 23;
+}
+
+/// Field positions of a successful login reply.
+mod login_field {
+    pub const SUCCESS_CODE: usize = 0;
+    pub const BTC_ADDRESS: usize = 1;
+    pub const PASSWORD: usize = 2;
+    pub const USER_ID: usize = 3;
+    pub const SESSION: usize = 4;
+
+    pub const COUNT: usize = 5;
+}
+
+/// Parses a decimal BTC amount such as `-0.00001234` into satoshis.
+pub fn parse_satoshis(amount: &str) -> Option<i64> {
+    let (negative, amount) = match amount.strip_prefix('-') {
+        Some(amount) => (true, amount),
+        None => (false, amount),
+    };
+    let (whole, fraction) = match amount.split_once('.') {
+        Some((_, "")) => return None,
+        Some((whole, fraction)) => (whole, fraction),
+        None => (amount, ""),
+    };
+    let is_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || fraction.len() > 8 || !is_digits(fraction) {
+        return None;
+    }
+
+    let fraction = format!("{fraction:0<8}").parse::<i64>().ok()?;
+    let satoshis = whole
+        .parse::<i64>()
+        .ok()?
+        .checked_mul(SATOSHIS_PER_BTC)?
+        .checked_add(fraction)?;
+
+    Some(if negative { -satoshis } else { satoshis })
+}
+
+pub fn to_btc(satoshis: i64) -> f32 {
+    (satoshis as f64 / SATOSHIS_PER_BTC as f64) as f32
+}
+
+/// Maps the `e<n>` code of a rejected bet to a typed error. The reply carries no amounts, so
+/// `InsufficientBalance` comes back zeroed for the client to fill in.
+fn bet_error(code: &str, raw: &str) -> BetError {
+    let reason = match code {
+        "e1" => {
+            return BetError::InsufficientBalance {
+                needed: 0.,
+                available: 0.,
+            }
+        }
+        "e2" => "stake below the minimum bet",
+        "e3" => "stake above the maximum bet",
+        "e4" => "multiplier out of range",
+        "e5" => "invalid client seed",
+        // The session or the CSRF token it's tied to is gone.
+        "e6" | "e7" => return BetError::AuthExpired,
+        _ => {
+            return BetError::BetRejected {
+                reason: raw.to_string(),
+            }
+        }
+    };
+
+    BetError::BetRejected {
+        reason: format!("{reason} ({code})"),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BetSiteResult {
+    pub result: bool,
+    pub rolled_number: u32,
+    pub user_balance: i64,
+    /// The stake on a loss, positive either way.
+    pub amount_won: i64,
+    pub server_seed_hash_next_roll: String,
+    pub client_seed_previous_roll: String,
+    pub nonce_next_roll: u64,
+    pub server_seed_previous_roll: String,
+    pub server_seed_hash_previous_roll: String,
+    pub previous_nonce: u64,
+    pub jackpot_result: u8,
+    pub jackpot_amount_won: i64,
+    pub bonus_account_balance_after_bet: i64,
+    pub bonus_account_wager_remaining: i64,
+    pub max_amount_bonus_eligible: i64,
+    pub max_bet: i64,
+    pub account_balance_before_bet: i64,
+    pub account_balance_after_bet: i64,
+    pub bonus_account_balance_before_bet: i64,
+}
+
+impl TryFrom<&str> for BetSiteResult {
+    type Error = BetError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        use bet_field::*;
+
+        let value = value.trim();
+        let fields = value.split(':').collect::<Vec<&str>>();
+        let parse_error = || BetError::Parse {
+            raw: value.to_string(),
+        };
+
+        let code = fields[SUCCESS_CODE];
+        if code.starts_with('e') {
+            return Err(bet_error(code, value));
+        }
+        if code != "s1" || fields.len() < COUNT {
+            return Err(parse_error());
+        }
+
+        let text = |index: usize| fields[index].to_string();
+        let number = |index: usize| fields[index].parse::<u64>().map_err(|_| parse_error());
+        let satoshis = |index: usize| parse_satoshis(fields[index]).ok_or_else(parse_error);
+
+        let rolled_number = fields[ROLLED_NUMBER]
+            .parse::<u32>()
+            .ok()
+            .filter(|number| *number <= 10_000)
+            .ok_or_else(parse_error)?;
+        let result = match fields[RESULT] {
+            "w" => true,
+            "l" => false,
+            _ => return Err(parse_error()),
+        };
+
+        Ok(Self {
+            result,
+            rolled_number,
+            user_balance: satoshis(USER_BALANCE)?,
+            amount_won: satoshis(AMOUNT_WON)?,
+            server_seed_hash_next_roll: text(SERVER_SEED_HASH_NEXT_ROLL),
+            client_seed_previous_roll: text(CLIENT_SEED_PREVIOUS_ROLL),
+            nonce_next_roll: number(NONCE_NEXT_ROLL)?,
+            server_seed_previous_roll: text(SERVER_SEED_PREVIOUS_ROLL),
+            server_seed_hash_previous_roll: text(SERVER_SEED_HASH_PREVIOUS_ROLL),
+            previous_nonce: number(PREVIOUS_NONCE)?,
+            jackpot_result: fields[JACKPOT_RESULT]
+                .parse::<u8>()
+                .map_err(|_| parse_error())?,
+            jackpot_amount_won: satoshis(JACKPOT_AMOUNT_WON)?,
+            bonus_account_balance_after_bet: satoshis(BONUS_ACCOUNT_BALANCE_AFTER_BET)?,
+            bonus_account_wager_remaining: satoshis(BONUS_ACCOUNT_WAGER_REMAINING)?,
+            max_amount_bonus_eligible: satoshis(MAX_AMOUNT_BONUS_ELIGIBLE)?,
+            max_bet: satoshis(MAX_BET)?,
+            account_balance_before_bet: satoshis(ACCOUNT_BALANCE_BEFORE_BET)?,
+            account_balance_after_bet: satoshis(ACCOUNT_BALANCE_AFTER_BET)?,
+            bonus_account_balance_before_bet: satoshis(BONUS_ACCOUNT_BALANCE_BEFORE_BET)?,
+        })
+    }
+}
+
+/// The cookies a successful login hands back.
+#[derive(Clone, Debug)]
+pub struct LoginReply {
+    pub btc_address: String,
+    pub password: Secret,
+    pub user_id: u64,
+    pub session: Secret,
+}
+
+impl TryFrom<&str> for LoginReply {
+    type Error = BetError;
+
+    /// Failed logins come back as `e:<message>`, anything but a complete `s` reply fails. The
+    /// error only names the reply code or field count, the rest may be credentials.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        use login_field::*;
+
+        let fields = value.trim().split(':').collect::<Vec<&str>>();
+        let failed = |reason: String| BetError::LoginFailed { reason };
+        match fields[SUCCESS_CODE] {
+            "s" => {}
+            "e" => return Err(failed("reply code \"e\"".to_string())),
+            _ => return Err(failed("unrecognized reply code".to_string())),
+        }
+        if fields.len() < COUNT || fields[1..COUNT].iter().any(|field| field.is_empty()) {
+            return Err(failed(format!(
+                "reply with {} of {COUNT} fields",
+                fields.iter().filter(|field| !field.is_empty()).count()
+            )));
+        }
+
+        Ok(Self {
+            btc_address: fields[BTC_ADDRESS].to_string(),
+            password: Secret::new(fields[PASSWORD].to_string()),
+            user_id: fields[USER_ID]
+                .parse::<u64>()
+                .map_err(|_| failed("reply with an unparseable user id".to_string()))?,
+            session: Secret::new(fields[SESSION].to_string()),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserStats {
+    pub balance: i64,
+    pub dice_profit: i64,
+    pub jackpot_spent: i64,
+    pub jackpot_winnings: i64,
+    pub lottery_spent: i64,
+    pub reward_points: u64,
+    pub rolls_played: u64,
+    pub status: String,
+    pub total_winnings: i64,
+    pub wagered: i64,
+}
+
+impl TryFrom<serde_json::Value> for UserStats {
+    type Error = BetError;
+
+    /// The stats API sends amounts as satoshi numbers, some of them as strings.
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        let parse_error = || BetError::Parse {
+            raw: value.to_string(),
+        };
+        let integer = |key: &str| match &value[key] {
+            serde_json::Value::String(number) => number.parse::<i64>().ok(),
+            number => number.as_i64(),
+        };
+        let satoshis = |key: &str| integer(key).ok_or_else(parse_error);
+        let count = |key: &str| {
+            integer(key)
+                .and_then(|count| u64::try_from(count).ok())
+                .ok_or_else(parse_error)
+        };
+
+        Ok(Self {
+            balance: satoshis("balance")?,
+            dice_profit: satoshis("dice_profit")?,
+            jackpot_spent: satoshis("jackpot_spent")?,
+            jackpot_winnings: satoshis("jackpot_winnings")?,
+            lottery_spent: satoshis("lottery_spent")?,
+            reward_points: count("reward_points")?,
+            rolls_played: count("rolls_played")?,
+            status: value["status"]
+                .as_str()
+                .ok_or_else(parse_error)?
+                .to_string(),
+            total_winnings: satoshis("total_winnings")?,
+            wagered: satoshis("wagered")?,
+        })
+    }
+}
+
+impl Default for UserStats {
+    fn default() -> Self {
+        Self {
+            balance: 400,
+            dice_profit: 0,
+            jackpot_spent: 0,
+            jackpot_winnings: 0,
+            lottery_spent: 0,
+            reward_points: 0,
+            rolls_played: 0,
+            status: String::new(),
+            total_winnings: 0,
+            wagered: 0,
+        }
+    }
+}
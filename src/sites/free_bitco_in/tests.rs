@@ -0,0 +1,238 @@
+//! Replies captured from the site, plus properties that keep the parser from panicking on
+//! whatever it's handed next.
+
+use proptest::prelude::*;
+
+use super::protocol::{parse_satoshis, SATOSHIS_PER_BTC};
+use super::*;
+
+const BET_WIN: &str = include_str!("corpus/bet_win.txt");
+const BET_LOSE: &str = include_str!("corpus/bet_lose.txt");
+const BET_EXTRA_FIELDS: &str = include_str!("corpus/bet_extra_fields.txt");
+const BET_TRUNCATED: &str = include_str!("corpus/bet_truncated.txt");
+const BET_INSUFFICIENT_BALANCE: &str = include_str!("corpus/bet_insufficient_balance.txt");
+const BET_SESSION_EXPIRED: &str = include_str!("corpus/bet_session_expired.txt");
+const BET_UNKNOWN_ERROR: &str = include_str!("corpus/bet_unknown_error.txt");
+const BET_HTML: &str = include_str!("corpus/bet_html.txt");
+const LOGIN_OK: &str = include_str!("corpus/login_ok.txt");
+const LOGIN_INCORRECT: &str = include_str!("corpus/login_incorrect.txt");
+const LOGIN_TRUNCATED: &str = include_str!("corpus/login_truncated.txt");
+const USER_STATS: &str = include_str!("corpus/user_stats.json");
+
+const BET_CORPUS: &[&str] = &[
+    BET_WIN,
+    BET_LOSE,
+    BET_EXTRA_FIELDS,
+    BET_TRUNCATED,
+    BET_INSUFFICIENT_BALANCE,
+    BET_SESSION_EXPIRED,
+    BET_UNKNOWN_ERROR,
+    BET_HTML,
+];
+
+fn format_satoshis(satoshis: i64) -> String {
+    let sign = if satoshis < 0 { "-" } else { "" };
+    let satoshis = satoshis.unsigned_abs();
+    let per_btc = SATOSHIS_PER_BTC as u64;
+
+    format!("{sign}{}.{:08}", satoshis / per_btc, satoshis % per_btc)
+}
+
+#[test]
+fn bet_replies_parse_into_named_fields() {
+    let win = BetSiteResult::try_from(BET_WIN).unwrap();
+    assert!(win.result);
+    assert_eq!(win.rolled_number, 7342);
+    assert_eq!(win.user_balance, 12346);
+    assert_eq!(win.amount_won, 1);
+    assert_eq!(win.nonce_next_roll, 1024);
+    assert_eq!(win.previous_nonce, 1023);
+    assert_eq!(win.client_seed_previous_roll, "lYypIPVEgzvCflWF");
+    assert_eq!(win.max_bet, 200_000);
+    assert_eq!(win.server_seed_hash_previous_roll.len(), 64);
+
+    let lose = BetSiteResult::try_from(BET_LOSE).unwrap();
+    assert!(!lose.result);
+    assert_eq!(lose.amount_won, 2);
+    assert_eq!(
+        lose.account_balance_before_bet - lose.account_balance_after_bet,
+        2
+    );
+
+    let bet_result: BetOutcome = lose.into();
+    assert!((bet_result.win_amount + 2e-8).abs() < 1e-12);
+}
+
+#[test]
+fn trailing_fields_are_ignored() {
+    let bet = BetSiteResult::try_from(BET_EXTRA_FIELDS).unwrap();
+    assert_eq!(bet.user_balance, 150_000_000);
+    assert_eq!(bet.amount_won, 75_000_000);
+}
+
+#[test]
+fn bad_bet_replies_are_typed() {
+    assert!(matches!(
+        BetSiteResult::try_from(BET_TRUNCATED),
+        Err(BetError::Parse { .. })
+    ));
+    assert!(matches!(
+        BetSiteResult::try_from(BET_HTML),
+        Err(BetError::Parse { .. })
+    ));
+    assert!(matches!(
+        BetSiteResult::try_from(BET_INSUFFICIENT_BALANCE),
+        Err(BetError::InsufficientBalance { .. })
+    ));
+    assert!(matches!(
+        BetSiteResult::try_from(BET_SESSION_EXPIRED),
+        Err(BetError::AuthExpired)
+    ));
+    assert!(matches!(
+        BetSiteResult::try_from(BET_UNKNOWN_ERROR),
+        Err(BetError::BetRejected { reason }) if reason.contains("e42")
+    ));
+}
+
+#[test]
+fn login_replies() {
+    let login = LoginReply::try_from(LOGIN_OK).unwrap();
+    assert_eq!(login.btc_address, "1BoatSLRHtKNngkdXEeobR76b53LETtpyT");
+    assert_eq!(login.user_id, 1234567);
+    assert_eq!(login.session.expose(), "aB3dE5fG7hJ9kL1mN3pQ");
+    assert!(!format!("{login:?}").contains("aB3dE5fG7hJ9kL1mN3pQ"));
+
+    for reply in [LOGIN_INCORRECT, LOGIN_TRUNCATED, "", "s::::"] {
+        assert!(matches!(
+            LoginReply::try_from(reply),
+            Err(BetError::LoginFailed { reason }) if !reason.contains("Incorrect") && !reason.contains("9c1f5d3b")
+        ));
+    }
+}
+
+#[test]
+fn user_stats_are_satoshis() {
+    let stats = UserStats::try_from(serde_json::from_str::<serde_json::Value>(USER_STATS).unwrap())
+        .unwrap();
+    assert_eq!(stats.balance, 12346);
+    assert_eq!(stats.dice_profit, -200);
+    assert_eq!(stats.reward_points, 17);
+    assert_eq!(to_btc(stats.balance), 0.00012346);
+}
+
+#[test]
+fn satoshi_amounts() {
+    assert_eq!(parse_satoshis("0.00000001"), Some(1));
+    assert_eq!(parse_satoshis("-1.5"), Some(-150_000_000));
+    assert_eq!(parse_satoshis("12"), Some(1_200_000_000));
+    for amount in ["", ".1", "1.", "0.000000001", "1e-8", "--1", "0,1", " 1"] {
+        assert_eq!(parse_satoshis(amount), None, "{amount:?}");
+    }
+}
+
+prop_compose! {
+    fn bet_reply()(
+        result in prop::bool::ANY,
+        rolled_number in 0u32..=10_000,
+        amounts in prop::collection::vec(0i64..21_000_000 * SATOSHIS_PER_BTC, 12),
+        nonce in 1u64..u64::MAX,
+        client_seed in "[A-Za-z0-9]{1,64}",
+    ) -> (String, BetSiteResult) {
+        let hash = "ab".repeat(32);
+        let fields = [
+            "s1".to_string(),
+            if result { "w" } else { "l" }.to_string(),
+            rolled_number.to_string(),
+            format_satoshis(amounts[0]),
+            format_satoshis(amounts[1]),
+            "0".to_string(),
+            hash.clone(),
+            client_seed.clone(),
+            nonce.to_string(),
+            "seed".to_string(),
+            hash.clone(),
+            client_seed.clone(),
+            (nonce - 1).to_string(),
+            "0".to_string(),
+            "0".to_string(),
+            format_satoshis(amounts[2]),
+            format_satoshis(amounts[3]),
+            format_satoshis(amounts[4]),
+            format_satoshis(amounts[5]),
+            format_satoshis(amounts[6]),
+            format_satoshis(amounts[7]),
+            format_satoshis(amounts[8]),
+            format_satoshis(amounts[9]),
+        ];
+        let expected = BetSiteResult {
+            result,
+            rolled_number,
+            user_balance: amounts[0],
+            amount_won: amounts[1],
+            server_seed_hash_next_roll: hash.clone(),
+            client_seed_previous_roll: client_seed,
+            nonce_next_roll: nonce,
+            server_seed_previous_roll: "seed".to_string(),
+            server_seed_hash_previous_roll: hash,
+            previous_nonce: nonce - 1,
+            jackpot_result: 0,
+            jackpot_amount_won: amounts[2],
+            bonus_account_balance_after_bet: amounts[3],
+            bonus_account_wager_remaining: amounts[4],
+            max_amount_bonus_eligible: amounts[5],
+            max_bet: amounts[6],
+            account_balance_before_bet: amounts[7],
+            account_balance_after_bet: amounts[8],
+            bonus_account_balance_before_bet: amounts[9],
+        };
+
+        (fields.join(":"), expected)
+    }
+}
+
+proptest! {
+    #[test]
+    fn well_formed_replies_round_trip((reply, expected) in bet_reply()) {
+        prop_assert_eq!(BetSiteResult::try_from(reply.as_str()).unwrap(), expected);
+    }
+
+    #[test]
+    fn satoshis_round_trip(satoshis in any::<i64>().prop_filter("i64::MIN has no positive", |satoshis| *satoshis != i64::MIN)) {
+        prop_assert_eq!(parse_satoshis(&format_satoshis(satoshis)), Some(satoshis));
+    }
+
+    #[test]
+    fn arbitrary_input_never_panics(reply in ".*") {
+        let _ = BetSiteResult::try_from(reply.as_str());
+        let _ = LoginReply::try_from(reply.as_str());
+    }
+
+    #[test]
+    fn colon_soup_never_panics(reply in "[sew0-9a-z.:-]{0,200}") {
+        let _ = BetSiteResult::try_from(reply.as_str());
+        let _ = LoginReply::try_from(reply.as_str());
+    }
+
+    /// Truncating the corpus or swapping a field for garbage, the way protocol drift would,
+    /// fails with a typed error or still parses.
+    #[test]
+    fn mutated_corpus_never_panics(
+        index in 0..BET_CORPUS.len(),
+        cut in any::<prop::sample::Index>(),
+        field in any::<prop::sample::Index>(),
+        garbage in ".{0,20}",
+    ) {
+        let reply = BET_CORPUS[index].trim();
+        let cut = reply
+            .char_indices()
+            .map(|(index, _)| index)
+            .nth(cut.index(reply.chars().count().max(1)))
+            .unwrap_or(0);
+        let _ = BetSiteResult::try_from(&reply[..cut]);
+
+        let mut fields = reply.split(':').map(str::to_string).collect::<Vec<String>>();
+        let field = field.index(fields.len());
+        fields[field] = garbage;
+        let _ = BetSiteResult::try_from(fields.join(":").as_str());
+    }
+}
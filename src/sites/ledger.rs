@@ -0,0 +1,451 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sites::audit::PairAudit;
+use crate::sites::{BetOutcome, SeedInfo, Sites};
+
+/// A bet as the site has it on record.
+#[derive(Clone, Debug)]
+pub struct SiteBet {
+    pub bet_id: String,
+    pub amount: f32,
+    pub profit: f32,
+}
+
+/// The site balance the ledger was last reconciled against and how far it got.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+struct Checkpoint {
+    bets: usize,
+    balance: f32,
+}
+
+/// The session moving from one currency to another after `bets` bets.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CurrencySwitch {
+    pub bets: usize,
+    pub from: String,
+    pub to: String,
+}
+
+/// A seed pair retired after `bets` bets, with what the site revealed of it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Rotation {
+    pub bets: usize,
+    pub retired: SeedInfo,
+    /// Server seeds keyed by hash.
+    #[serde(default)]
+    pub revealed: Vec<(String, String)>,
+}
+
+/// Every bet a session placed, every currency switch and seed rotation, in order.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Ledger {
+    /// The site the bets were placed on, `None` in ledgers from before it was recorded.
+    #[serde(default)]
+    site: Option<Sites>,
+    bets: Vec<BetOutcome>,
+    /// Keyed by currency symbol, each currency is reconciled against its own balance.
+    #[serde(default)]
+    checkpoints: HashMap<String, Checkpoint>,
+    #[serde(default)]
+    switches: Vec<CurrencySwitch>,
+    #[serde(default)]
+    rotations: Vec<Rotation>,
+    #[serde(default)]
+    audits: Vec<PairAudit>,
+    /// House edge in percent by currency symbol, as the site advertised it.
+    #[serde(default)]
+    house_edges: HashMap<String, f32>,
+    /// Keys of the first `indexed` bets, rebuilt after loading, see `index`.
+    #[serde(skip)]
+    keys: HashSet<String>,
+    #[serde(skip)]
+    indexed: usize,
+    /// Indices of indexed bets whose key came up before, in order.
+    #[serde(skip)]
+    repeats: Vec<usize>,
+}
+
+/// What a reconciliation of one currency found since the previous one.
+#[derive(Clone, Debug, Default)]
+pub struct Reconciliation {
+    pub symbol: String,
+    /// Ledger bets since the previous reconciliation.
+    pub bets: usize,
+    pub site_balance: f32,
+    /// The previous site balance plus the ledger's profit since, `None` on the first check.
+    pub expected_balance: Option<f32>,
+    /// Nonces skipped within a seed pair, bets the site took that never reached the ledger.
+    pub missing: u64,
+    /// Bets recorded more than once.
+    pub duplicates: Vec<String>,
+    /// Bets the site has no record of.
+    pub unknown: Vec<String>,
+    /// Bets the site recorded with another amount or profit.
+    pub mismatched: Vec<String>,
+}
+
+impl Reconciliation {
+    /// Site balance minus the expected one, sessions sharing an account and currency see each
+    /// other's bets as drift.
+    pub fn drift(&self) -> f32 {
+        self.expected_balance
+            .map_or(0., |expected_balance| self.site_balance - expected_balance)
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.missing == 0
+            && self.duplicates.is_empty()
+            && self.unknown.is_empty()
+            && self.mismatched.is_empty()
+    }
+}
+
+impl fmt::Display for Reconciliation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} bets, site balance {:.8}",
+            self.symbol, self.bets, self.site_balance
+        )?;
+        if let Some(expected_balance) = self.expected_balance {
+            write!(
+                f,
+                ", expected {expected_balance:.8} (drift {:+.8})",
+                self.drift()
+            )?;
+        }
+        if self.missing > 0 {
+            write!(f, ", {} missing", self.missing)?;
+        }
+        for (label, bets) in [
+            ("duplicated", &self.duplicates),
+            ("unknown to the site", &self.unknown),
+            ("mismatched", &self.mismatched),
+        ] {
+            if !bets.is_empty() {
+                write!(f, ", {label}: {}", bets.join(" "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Identifies a bet, by the site's id where it has one and by its roll otherwise.
+pub fn bet_key(bet: &BetOutcome) -> String {
+    if bet.bet_id.is_empty() {
+        format!(
+            "{}:{}#{}",
+            bet.hash_previous_roll, bet.client_seed, bet.nonce
+        )
+    } else {
+        bet.bet_id.clone()
+    }
+}
+
+fn differs(local: f32, site: f32) -> bool {
+    (local - site).abs() > 1e-9 + 1e-6 * site.abs()
+}
+
+impl Ledger {
+    pub fn new(site: Sites) -> Self {
+        Self {
+            site: Some(site),
+            ..Self::default()
+        }
+    }
+
+    /// Takes `site` as the ledger's unless it already records one.
+    pub fn or_site(mut self, site: Sites) -> Self {
+        self.site.get_or_insert(site);
+
+        self
+    }
+
+    pub fn site(&self) -> Option<Sites> {
+        self.site
+    }
+
+    pub fn record(&mut self, bet_result: BetOutcome) {
+        self.bets.push(bet_result);
+        self.index();
+    }
+
+    /// Adds the keys of the bets since the last call to the index, so repeats are found
+    /// without going over the whole ledger again.
+    fn index(&mut self) {
+        for (index, bet) in self.bets.iter().enumerate().skip(self.indexed) {
+            if !self.keys.insert(bet_key(bet)) {
+                self.repeats.push(index);
+            }
+        }
+        self.indexed = self.bets.len();
+    }
+
+    pub fn bets(&self) -> &[BetOutcome] {
+        &self.bets
+    }
+
+    pub fn switches(&self) -> &[CurrencySwitch] {
+        &self.switches
+    }
+
+    pub fn audits(&self) -> &[PairAudit] {
+        &self.audits
+    }
+
+    pub fn rotations(&self) -> &[Rotation] {
+        &self.rotations
+    }
+
+    pub fn house_edges(&self) -> &HashMap<String, f32> {
+        &self.house_edges
+    }
+
+    pub fn record_house_edge(&mut self, symbol: &str, house_edge: f32) {
+        self.house_edges.insert(symbol.to_string(), house_edge);
+    }
+
+    /// Records the end of the `retired` pair and returns its bets.
+    pub fn retire_pair(&mut self, retired: SeedInfo) -> &[BetOutcome] {
+        let start = self.rotations.last().map_or(0, |rotation| rotation.bets);
+        self.rotations.push(Rotation {
+            bets: self.bets.len(),
+            retired,
+            revealed: Vec::new(),
+        });
+
+        &self.bets[start..]
+    }
+
+    /// Keeps a server seed the site revealed of the latest retired pair.
+    pub fn record_reveal(&mut self, server_seed_hash: String, server_seed: String) {
+        if let Some(rotation) = self.rotations.last_mut() {
+            rotation.revealed.push((server_seed_hash, server_seed));
+        }
+    }
+
+    pub fn record_audit(&mut self, audit: PairAudit) {
+        self.audits.push(audit);
+    }
+
+    /// Takes `site_balance` as the starting point of `symbol` unless it was reconciled before.
+    pub fn open(&mut self, symbol: &str, site_balance: f32) {
+        let bets = self.bets.len();
+        self.checkpoints
+            .entry(symbol.to_string())
+            .or_insert(Checkpoint {
+                bets,
+                balance: site_balance,
+            });
+    }
+
+    /// Records a currency switch, `site_balance` is the new currency's.
+    pub fn switch(&mut self, from: &str, to: &str, site_balance: f32) {
+        self.switches.push(CurrencySwitch {
+            bets: self.bets.len(),
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+        self.open(to, site_balance);
+    }
+
+    fn start(&self, symbol: &str) -> usize {
+        self.checkpoints
+            .get(symbol)
+            .map_or(0, |checkpoint| checkpoint.bets)
+    }
+
+    /// Site ids of the newest `limit` bets in `symbol` since its last reconciliation.
+    pub fn unreconciled_ids(&self, symbol: &str, limit: usize) -> Vec<String> {
+        self.bets[self.start(symbol)..]
+            .iter()
+            .rev()
+            .filter(|bet| bet.symbol == symbol && !bet.bet_id.is_empty())
+            .take(limit)
+            .map(|bet| bet.bet_id.clone())
+            .collect()
+    }
+
+    /// Diffs the bets in `symbol` since its last reconciliation against the site and starts
+    /// the next one from `site_balance`. `site_bets` holds whichever of `checked` the site
+    /// found, `None` on sites that can't look bets up.
+    pub fn reconcile(
+        &mut self,
+        symbol: &str,
+        site_balance: f32,
+        checked: &[String],
+        site_bets: Option<&[SiteBet]>,
+    ) -> Reconciliation {
+        self.index();
+        let start = self.start(symbol);
+        let checkpoint = self.checkpoints.get(symbol).copied();
+        let bets = self.bets[start..]
+            .iter()
+            .filter(|bet| bet.symbol == symbol)
+            .collect::<Vec<&BetOutcome>>();
+        let mut reconciliation = Reconciliation {
+            symbol: symbol.to_string(),
+            bets: bets.len(),
+            site_balance,
+            expected_balance: checkpoint.map(|checkpoint| {
+                checkpoint.balance + bets.iter().map(|bet| bet.win_amount).sum::<f32>()
+            }),
+            ..Reconciliation::default()
+        };
+
+        // Seed pairs can span currencies, so nonces run across all bets. Each gap is counted by
+        // the currency of the bet after it, the one before the checkpoint included. crypto.games
+        // rolls ignore the nonce.
+        let window = &self.bets[start.saturating_sub(1)..];
+        for pair in window.windows(2) {
+            if self.site != Some(Sites::CryptoGames)
+                && pair[1].symbol == symbol
+                && pair[0].client_seed == pair[1].client_seed
+                && pair[1].nonce > pair[0].nonce + 1
+            {
+                reconciliation.missing += (pair[1].nonce - pair[0].nonce - 1) as u64;
+            }
+        }
+
+        let repeats = &self.repeats[self.repeats.partition_point(|&index| index < start)..];
+        for &index in repeats {
+            if self.bets[index].symbol == symbol {
+                reconciliation.duplicates.push(bet_key(&self.bets[index]));
+            }
+        }
+
+        if let Some(site_bets) = site_bets {
+            for bet_id in checked {
+                let Some(site_bet) = site_bets.iter().find(|site_bet| site_bet.bet_id == *bet_id)
+                else {
+                    reconciliation.unknown.push(bet_id.clone());
+                    continue;
+                };
+                let Some(bet) = bets.iter().find(|bet| bet.bet_id == *bet_id) else {
+                    continue;
+                };
+                if differs(bet.bet_amount, site_bet.amount)
+                    || differs(bet.win_amount, site_bet.profit)
+                {
+                    reconciliation.mismatched.push(bet_id.clone());
+                }
+            }
+        }
+
+        self.checkpoints.insert(
+            symbol.to_string(),
+            Checkpoint {
+                bets: self.bets.len(),
+                balance: site_balance,
+            },
+        );
+
+        reconciliation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sites::BetKind;
+
+    fn bet(bet_id: &str, nonce: u32, win_amount: f32) -> BetOutcome {
+        BetOutcome {
+            bet_id: bet_id.to_string(),
+            hash_previous_roll: "hash".to_string(),
+            hash_next_roll: "hash".to_string(),
+            client_seed: "client".to_string(),
+            nonce,
+            symbol: "BTC".to_string(),
+            result: win_amount > 0.,
+            is_high: true,
+            kind: BetKind::Over,
+            number: 0,
+            threshold: 0,
+            chance: 49.5,
+            payout: 2.,
+            bet_amount: 0.001,
+            win_amount,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn gaps_and_repeats_are_reported_once() {
+        let mut ledger = Ledger::default();
+        ledger.open("BTC", 1.);
+        ledger.record(bet("", 1, 0.001));
+        ledger.record(bet("", 2, -0.001));
+        let report = ledger.reconcile("BTC", 1., &[], None);
+        assert_eq!(report.expected_balance, Some(1.));
+        assert!(report.is_clean());
+
+        ledger.record(bet("", 5, 0.001));
+        ledger.record(bet("", 5, 0.001));
+        let report = ledger.reconcile("BTC", 1.002, &[], None);
+        assert_eq!(report.missing, 2);
+        assert_eq!(report.duplicates, vec!["hash:client#5".to_string()]);
+        assert!(report.drift().abs() < 1e-6);
+
+        let report = ledger.reconcile("BTC", 1.002, &[], None);
+        assert_eq!(report.bets, 0);
+        assert!(report.is_clean());
+
+        // A resumed ledger still knows the bets recorded before.
+        let mut ledger: Ledger =
+            serde_json::from_value(serde_json::to_value(&ledger).unwrap()).unwrap();
+        ledger.record(bet("", 2, 0.));
+        let report = ledger.reconcile("BTC", 1.002, &[], None);
+        assert_eq!(report.duplicates, vec!["hash:client#2".to_string()]);
+    }
+
+    #[test]
+    fn gaps_after_the_first_nonce_count() {
+        let mut ledger = Ledger::new(Sites::DuckDiceIo);
+        ledger.open("BTC", 1.);
+        ledger.record(bet("", 0, 0.));
+        ledger.record(bet("", 3, 0.));
+        assert_eq!(ledger.reconcile("BTC", 1., &[], None).missing, 2);
+
+        // crypto.games bets all carry nonce 0.
+        let mut ledger = Ledger::new(Sites::CryptoGames);
+        ledger.open("BTC", 1.);
+        ledger.record(bet("a", 0, 0.));
+        ledger.record(bet("b", 3, 0.));
+        assert_eq!(ledger.reconcile("BTC", 1., &[], None).missing, 0);
+    }
+
+    #[test]
+    fn site_survives_a_snapshot() {
+        let ledger = serde_json::to_value(Ledger::new(Sites::CryptoGames)).unwrap();
+        let ledger: Ledger = serde_json::from_value(ledger).unwrap();
+        assert_eq!(ledger.site(), Some(Sites::CryptoGames));
+
+        let ledger: Ledger = serde_json::from_str(r#"{"bets": []}"#).unwrap();
+        assert_eq!(ledger.site(), None);
+        assert_eq!(ledger.or_site(Sites::Windice).site(), Some(Sites::Windice));
+    }
+
+    #[test]
+    fn site_records_are_matched_by_id() {
+        let mut ledger = Ledger::default();
+        ledger.open("BTC", 1.);
+        ledger.record(bet("a", 0, 0.001));
+        ledger.record(bet("b", 0, -0.001));
+        let checked = ledger.unreconciled_ids("BTC", 10);
+        assert_eq!(checked, vec!["b".to_string(), "a".to_string()]);
+
+        let site_bets = [SiteBet {
+            bet_id: "b".to_string(),
+            amount: 0.001,
+            profit: -0.002,
+        }];
+        let report = ledger.reconcile("BTC", 1., &checked, Some(&site_bets));
+        assert_eq!(report.unknown, vec!["a".to_string()]);
+        assert_eq!(report.mismatched, vec!["b".to_string()]);
+    }
+}
@@ -1,128 +1,642 @@
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use async_trait::async_trait;
+use clap::ValueEnum;
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ConfigError, SiteConfig, TomlConfig};
+use crate::currency::Currency;
+use crate::strategies;
+use ledger::{Reconciliation, SiteBet};
+use session::{BettingSession, ResetPolicy};
 
+pub mod audit;
+pub mod bet_kind;
+pub mod capabilities;
 pub mod crypto_games;
 pub mod duck_dice;
-pub mod fake_test;
 pub mod free_bitco_in;
+pub mod ledger;
+#[cfg(test)]
+mod mock;
+pub mod paper;
+pub mod provably_fair;
+pub mod request;
+pub mod seed_chain;
+pub mod session;
 pub mod windice;
 
+pub use bet_kind::{chance_rolls, BetKind, MAX_ROLL};
+pub use capabilities::SiteCapabilities;
+
 #[derive(Debug)]
 pub enum BetError {
+    /// The site dropped the request, the bet is skipped.
     EmptyReply,
-    Failed,
-    LoginFailed,
+    /// `reason` never holds the reply itself, login replies carry credentials.
+    LoginFailed {
+        reason: String,
+    },
     ReqwestError(reqwest::Error),
+    InsufficientBalance {
+        needed: f32,
+        available: f32,
+    },
+    RateLimited {
+        retry_after: Option<Duration>,
+    },
+    HttpStatus(StatusCode),
+    /// A reply that doesn't match the site's format, `raw` is the body as received.
+    Parse {
+        raw: String,
+    },
+    /// The session or API key is no longer accepted, logging in again may fix it.
+    AuthExpired,
+    BetRejected {
+        reason: String,
+    },
+    /// The site hasn't revealed the server seed behind `server_seed_hash`, the pair may still
+    /// be active.
+    SeedUnavailable {
+        server_seed_hash: String,
+    },
+    /// A transient failure that outlasted the request layer's retries, `last` is the final one.
+    RetriesExhausted {
+        attempts: u32,
+        last: Box<BetError>,
+    },
+    UnsupportedCurrency {
+        site: Sites,
+        currency: Currency,
+    },
+    UnsupportedBetKind {
+        site: Sites,
+        kind: BetKind,
+    },
+    /// The audit of a retired seed pair found `failed` bets its seeds don't account for,
+    /// betting stops.
+    UnfairRolls {
+        server_seed_hash: String,
+        failed: usize,
+    },
+}
+
+impl std::fmt::Display for BetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyReply => write!(f, "site returned an empty reply"),
+            Self::LoginFailed { reason } => write!(f, "login failed, {reason}"),
+            Self::ReqwestError(err) => write!(f, "request failed: {err}"),
+            Self::InsufficientBalance { needed, available } => write!(
+                f,
+                "insufficient balance, the bet needs {needed:.8} but only {available:.8} is available"
+            ),
+            Self::RateLimited {
+                retry_after: Some(retry_after),
+            } => write!(f, "rate limited, retry after {}s", retry_after.as_secs()),
+            Self::RateLimited { retry_after: None } => write!(f, "rate limited"),
+            Self::HttpStatus(status) => write!(f, "site replied with {status}"),
+            Self::Parse { raw } => {
+                let raw = raw.chars().take(200).collect::<String>();
+                write!(f, "unable to parse the site reply: {raw:?}")
+            }
+            Self::AuthExpired => write!(f, "session expired or API key rejected"),
+            Self::BetRejected { reason } => write!(f, "bet rejected: {reason}"),
+            Self::SeedUnavailable { server_seed_hash } => {
+                write!(f, "no revealed server seed for {server_seed_hash}")
+            }
+            Self::RetriesExhausted { attempts, last } => {
+                write!(f, "gave up after {attempts} attempts: {last}")
+            }
+            Self::UnsupportedCurrency { site, currency } => {
+                write!(f, "{currency} is not supported by {site:?}")
+            }
+            Self::UnsupportedBetKind { site, kind } => {
+                write!(f, "{kind} bets are not supported by {site:?}")
+            }
+            Self::UnfairRolls {
+                server_seed_hash,
+                failed,
+            } => write!(
+                f,
+                "{failed} bet(s) under server seed {server_seed_hash} failed the fairness audit"
+            ),
+        }
+    }
 }
 
+impl std::error::Error for BetError {}
+
 impl From<reqwest::Error> for BetError {
+    /// Drops the URL since the API keys of some sites travel in the query string.
     fn from(value: reqwest::Error) -> Self {
-        Self::ReqwestError(value)
+        Self::ReqwestError(value.without_url())
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct BetResult {
+/// A random alphanumeric seed of `len` characters.
+pub fn random_seed(len: usize) -> String {
+    rand::rng()
+        .sample_iter(rand::distr::Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// Reads a `Retry-After` header given in seconds.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Turns error statuses into typed errors, 400 and 422 replies carry the site's reason.
+pub async fn check_status(res: reqwest::Response) -> Result<reqwest::Response, BetError> {
+    let status = res.status();
+    if status.is_success() {
+        return Ok(res);
+    }
+
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => Err(BetError::RateLimited {
+            retry_after: retry_after(res.headers()),
+        }),
+        StatusCode::UNAUTHORIZED => Err(BetError::AuthExpired),
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Err(BetError::BetRejected {
+            reason: res.text().await?,
+        }),
+        _ => Err(BetError::HttpStatus(status)),
+    }
+}
+
+/// Deserializes a reply body, keeping the raw body on failure.
+pub async fn read_json<T: DeserializeOwned>(res: reqwest::Response) -> Result<T, BetError> {
+    let raw = res.text().await?;
+
+    serde_json::from_str(&raw).map_err(|_| BetError::Parse { raw })
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BetOutcome {
+    /// The site's id for the bet, empty on sites without one.
+    #[serde(default)]
+    pub bet_id: String,
+    /// The retired pair's server seed hash. Sites with a server seed per bet put this roll's there instead, freebitco.in its hash and crypto.games the
+    /// revealed seed itself, see `seed_chain::SeedChain`.
     pub hash_previous_roll: String,
+    /// The server seed hash the next bet will be rolled under.
     pub hash_next_roll: String,
     pub client_seed: String,
     pub nonce: u32,
     pub symbol: String,
     pub result: bool,
     pub is_high: bool,
+    /// `Over` on bets recorded before kinds were, `is_high` tells those apart.
+    #[serde(default)]
+    pub kind: BetKind,
     pub number: u32,
     pub threshold: u32,
     pub chance: f32,
     pub payout: f32,
     pub bet_amount: f32,
     pub win_amount: f32,
+    /// Milliseconds since the Unix epoch, the site's time where it reports one.
+    #[serde(default)]
+    pub timestamp: u64,
 }
 
-impl From<free_bitco_in::BetSiteResult> for BetResult {
+impl BetOutcome {
+    /// Fills in what the site's reply leaves out from the decision the bet was placed with,
+    /// every client calls this so outcomes look the same whatever the site.
+    pub fn complete(mut self, decision: &BetDecision, site: Sites) -> Self {
+        if self.chance == 0. {
+            self.chance = decision.chance;
+        }
+        if self.payout == 0. {
+            self.payout = decision.multiplier;
+        }
+        if self.bet_amount == 0. {
+            self.bet_amount = decision.amount;
+        }
+        if self.threshold == 0 {
+            self.threshold = decision.kind.threshold(self.chance, site.max_roll());
+        }
+        if self.timestamp == 0 {
+            self.timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_millis() as u64);
+        }
+        self.kind = decision.kind;
+        self.is_high = decision.kind == BetKind::Over;
+
+        self
+    }
+}
+
+impl From<free_bitco_in::BetSiteResult> for BetOutcome {
     fn from(value: free_bitco_in::BetSiteResult) -> Self {
         Self {
+            bet_id: String::new(),
             hash_previous_roll: value.server_seed_hash_previous_roll,
             hash_next_roll: value.server_seed_hash_next_roll,
             client_seed: value.client_seed_previous_roll.clone(),
-            nonce: value.nonce_next_roll.clone().parse::<u32>().unwrap_or(0),
+            nonce: value.previous_nonce as u32,
             symbol: "BTC".to_string(),
             result: value.result,
-            is_high: value.rolled_number > 5000 && value.result,
             number: value.rolled_number,
-            // We can't get this number from freebitco.in without external data so we won't include
-            // that data.
+            // The reply doesn't echo the bet, `BetOutcome::complete` fills these in.
+            is_high: false,
+            kind: BetKind::default(),
             threshold: 0,
-            // Likewise with chance.
             chance: 0.,
-            // And for this as well.
             payout: 0.,
-            // You guessed it.
             bet_amount: 0.,
-            win_amount: value.amount_won,
+            win_amount: free_bitco_in::to_btc(if value.result {
+                value.amount_won
+            } else {
+                -value.amount_won
+            }),
+            timestamp: 0,
         }
     }
 }
 
-impl From<duck_dice::BetMakeResponse> for BetResult {
+impl From<duck_dice::BetMakeResponse> for BetOutcome {
     fn from(value: duck_dice::BetMakeResponse) -> Self {
         Self {
+            bet_id: value.bet.id.clone(),
             hash_previous_roll: value.bet.previous_hash.clone(),
             hash_next_roll: value.bet.hash.clone(),
             client_seed: String::new(),
             nonce: value.bet.nonce as u32,
             symbol: value.bet.symbol,
             result: value.bet.result,
-            is_high: value.bet.choice.chars().next().unwrap_or(' ') == '>',
+            is_high: value.bet.choice.starts_with('>'),
+            kind: BetKind::from_high(value.bet.choice.starts_with('>')),
             number: value.bet.number,
             threshold: 0,
             chance: value.bet.chance,
             payout: value.bet.payout,
             bet_amount: value.bet.bet_amount,
             win_amount: value.bet.profit,
+            timestamp: value.bet.created,
         }
     }
 }
 
-impl From<crypto_games::BetSiteResult> for BetResult {
+impl From<crypto_games::BetSiteResult> for BetOutcome {
     fn from(value: crypto_games::BetSiteResult) -> Self {
         Self {
+            bet_id: value.bet_id.to_string(),
             hash_previous_roll: value.server_seed.clone(),
             hash_next_roll: value.next_server_seed_hash.clone(),
-            client_seed: "BeO2jZRd4nidPz4U40e2G7hT22s9GA".to_string(),
+            client_seed: String::new(),
             nonce: 0,
-            symbol: "SOL".to_string(),
+            symbol: String::new(),
             result: value.profit > 0.,
-            is_high: value.roll as u32 > 5000 && value.profit > 0.,
-            number: value.roll as u32,
+            // The site rolls to thousandths, rounding first keeps float error from the truncation
+            // to hundredths `CryptoGamesRoll` recomputes.
+            number: (value.roll * 1000.).round() as u32 / 10,
+            // Only the payout is echoed, `BetOutcome::complete` fills the rest in.
+            is_high: false,
+            kind: BetKind::default(),
             threshold: 0,
             chance: 0.,
             payout: value.payout as f32,
             bet_amount: 0.,
             win_amount: value.profit as f32,
+            timestamp: 0,
+        }
+    }
+}
+
+impl From<windice::RollResult> for BetOutcome {
+    fn from(value: windice::RollResult) -> Self {
+        let is_high = value.game == "in" && value.high == 9999 && value.low > 0;
+        let kind = match (value.game.as_str(), value.low, value.high) {
+            ("out", low, high) => BetKind::OutOfRange(low, high),
+            _ if is_high => BetKind::Over,
+            (_, 0, _) => BetKind::Under,
+            (_, low, high) => BetKind::InRange(low, high),
+        };
+        // The chance actually placed, the decision's may fall between two rolls.
+        let width = value.high.saturating_sub(value.low) + 1;
+        let chance = match kind {
+            BetKind::OutOfRange(..) => (MAX_ROLL + 1).saturating_sub(width),
+            _ => width,
+        } as f32
+            / 100.;
+
+        Self {
+            bet_id: String::new(),
+            // Rolls only carry the active pair's hash, `Windice` fills the retired one in.
+            hash_previous_roll: String::new(),
+            hash_next_roll: value.hash,
+            client_seed: String::new(),
+            nonce: value.nonce as u32,
+            symbol: value.curr.to_uppercase(),
+            result: value.win > 0.,
+            is_high,
+            kind,
+            number: value.result,
+            threshold: if is_high { value.low } else { value.high + 1 },
+            chance,
+            payout: value.payout as f32,
+            bet_amount: value.bet as f32,
+            win_amount: (value.win - value.bet) as f32,
+            timestamp: 0,
+        }
+    }
+}
+
+/// A bet picked by the session's strategy, ready to be placed once the site's limits are
+/// applied, see `SiteCapabilities::clamp`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BetDecision {
+    pub amount: f32,
+    /// Win chance in percent, pinned by the kind on range bets.
+    pub chance: f32,
+    /// Payout multiplier for `chance` after the site's house edge.
+    pub multiplier: f32,
+    pub kind: BetKind,
+}
+
+impl BetDecision {
+    pub fn threshold(&self, site: Sites) -> u32 {
+        self.kind.threshold(self.chance, site.max_roll())
+    }
+}
+
+/// The seed pair the next bet is rolled from.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SeedInfo {
+    pub server_seed_hash: String,
+    pub client_seed: String,
+    pub nonce: u64,
+}
+
+/// Retired pairs a client remembers, enough to audit the seeds of a long session.
+const SEED_MAP_SIZE: usize = 256;
+
+/// Values keyed by server seed hash, e.g. revealed server seeds, only the newest are kept.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SeedMap(VecDeque<(String, String)>);
+
+impl SeedMap {
+    pub fn insert(&mut self, server_seed_hash: String, value: String) {
+        if server_seed_hash.is_empty() {
+            return;
+        }
+
+        self.0.retain(|(hash, _)| *hash != server_seed_hash);
+        self.0.push_back((server_seed_hash, value));
+        if self.0.len() > SEED_MAP_SIZE {
+            self.0.pop_front();
         }
     }
+
+    pub fn get(&self, server_seed_hash: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(hash, _)| hash == server_seed_hash)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CurrencyBalance {
+    pub symbol: String,
+    pub amount: f32,
 }
 
+/// The API calls of a site and nothing else, `session::BettingSession` does the bookkeeping.
 #[async_trait]
-pub trait Site {
+pub trait SiteClient: Send {
+    fn site(&self) -> Sites;
+    /// Symbol of the currency bets are placed in.
+    fn symbol(&self) -> String;
+    /// Limits in the session's currency, fetched at login where the site reports them.
+    fn capabilities(&self) -> SiteCapabilities;
+    /// Fits the strategy's chance and multiplier to what the site accepts, returns
+    /// `(chance, multiplier)`.
+    fn odds(&self, chance: f32, multiplier: f32) -> (f32, f32);
     async fn login(&mut self) -> Result<(), BetError>;
-    async fn do_bet(&mut self, prediction: f32, confidence: f32) -> Result<BetResult, BetError>;
-    fn on_win(&mut self, bet_result: &BetResult);
-    fn on_lose(&mut self, bet_result: &BetResult);
-    fn get_history(&self) -> Vec<BetResult>;
+    async fn place_bet(&mut self, wager: &BetDecision) -> Result<BetOutcome, BetError>;
+    async fn fetch_balances(&mut self) -> Result<Vec<CurrencyBalance>, BetError>;
+    /// Starts a new seed pair.
+    async fn rotate_seed(&mut self) -> Result<(), BetError>;
+    fn seed_info(&self) -> SeedInfo;
+    /// The server seed of a retired pair, see `BetError::SeedUnavailable`.
+    async fn fetch_revealed_seed(&mut self, server_seed_hash: &str) -> Result<String, BetError>;
+    /// Moves bets to `currency`, sites with a single currency only accept their own.
+    async fn switch_currency(&mut self, currency: &Currency) -> Result<(), BetError> {
+        if currency.to_string() == self.symbol() {
+            return Ok(());
+        }
+
+        Err(BetError::UnsupportedCurrency {
+            site: self.site(),
+            currency: currency.clone(),
+        })
+    }
+    /// The site's record of `bet_ids`, leaving out the ones it doesn't know, `None` on sites
+    /// without a bet lookup.
+    async fn fetch_bets(&mut self, _bet_ids: &[String]) -> Result<Option<Vec<SiteBet>>, BetError> {
+        Ok(None)
+    }
+    /// Serializes the client's seed state, see `Site::save_state`.
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+    fn load_state(&mut self, _state: serde_json::Value) -> Result<(), serde_json::Error> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+pub trait Site: Send {
+    async fn login(&mut self) -> Result<(), BetError>;
+    /// Logs in again after `AuthExpired`, the bookkeeping and strategy state carry on.
+    async fn reauthenticate(&mut self) -> Result<(), BetError>;
+    /// Picks the next bet with the strategy, places it and records it in the history.
+    async fn do_bet(&mut self, prediction: f32, confidence: f32) -> Result<BetOutcome, BetError>;
+    fn on_win(&mut self, bet_result: &BetOutcome);
+    fn on_lose(&mut self, bet_result: &BetOutcome);
+    /// The pair the next bet is rolled from.
+    fn seed_info(&self) -> SeedInfo;
+    /// Moves to a new client seed and returns the retired pair.
+    async fn rotate_seed(&mut self) -> Result<SeedInfo, BetError>;
+    /// The server seed of the retired pair committed to by `server_seed_hash`.
+    async fn revealed_seed(&mut self, server_seed_hash: &str) -> Result<String, BetError>;
+    /// Waits for the audits of retired pairs still running and records them in the ledger.
+    async fn finish_audits(&mut self) -> Result<(), BetError>;
+    /// Diffs the bets placed since the last call against the site's balance and bet history.
+    async fn reconcile(&mut self) -> Result<Reconciliation, BetError>;
+    fn set_strategy_params(&mut self, params: toml::Table) -> Result<(), toml::de::Error>;
+    /// Serializes the local bookkeeping, history and strategy state, see `state::SessionSnapshot`.
+    fn save_state(&self) -> serde_json::Value;
+    /// Restores a `save_state` value, called after `login`.
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error>;
+    fn get_history(&self) -> Vec<BetOutcome>;
     fn get_history_size(&self) -> usize;
     fn get_rolls(&self) -> u64;
     fn get_current_bet(&self) -> f32;
     fn get_current_multiplier(&self) -> f32;
     fn get_profit(&self) -> f32;
     fn get_balance(&self) -> f32;
+    /// Balances of every currency the account holds.
+    async fn list_balances(&mut self) -> Result<Vec<CurrencyBalance>, BetError>;
+    /// Moves the session to `currency`, keeping the profit and history of each currency apart
+    /// and recording the switch in the ledger.
+    async fn switch_currency(&mut self, currency: &Currency) -> Result<(), BetError>;
+    /// Symbol of the currency the session bets in.
+    fn get_symbol(&self) -> String;
+    fn capabilities(&self) -> SiteCapabilities;
+    /// Profit per currency the session bet in, its current one first.
+    fn get_currency_profits(&self) -> Vec<(String, f32)>;
 }
 
 pub trait SiteCurrency {
     fn get_min_bet(&self) -> f32;
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize, ValueEnum)]
 pub enum Sites {
     DuckDiceIo,
     CryptoGames,
     FreeBitcoIn,
+    Windice,
+}
+
+impl Sites {
+    /// House edge in percent.
+    pub fn house_edge(self) -> f32 {
+        match self {
+            Self::DuckDiceIo => 1.,
+            Self::CryptoGames => 1.,
+            Self::FreeBitcoIn => 5.,
+            Self::Windice => 1.,
+        }
+    }
+
+    /// The highest roll, freebitco.in rolls `0..=10000` and the others `0..=MAX_ROLL`.
+    pub fn max_roll(self) -> u32 {
+        match self {
+            Self::FreeBitcoIn => 10000,
+            _ => MAX_ROLL,
+        }
+    }
+
+    /// Whether the site takes `BetKind::InRange` and `BetKind::OutOfRange` bets.
+    pub fn ranges(self) -> bool {
+        self == Self::Windice
+    }
+
+    /// Requests a second the site tolerates across all sessions, see `request::Requester`.
+    pub fn requests_per_second(self) -> f64 {
+        match self {
+            Self::DuckDiceIo => 4.,
+            Self::CryptoGames => 5.,
+            Self::FreeBitcoIn => 2.,
+            Self::Windice => 5.,
+        }
+    }
+}
+
+/// Builds the single enabled site from the config, its client is wrapped in a `PaperSite`
+/// when `paper` is set.
+pub fn from_config(config: &TomlConfig, paper: bool) -> Result<Box<dyn Site>, ConfigError> {
+    let active_site = config.active_site()?;
+    let (mut client, reset_policy): (Box<dyn SiteClient>, _) = match active_site {
+        Sites::CryptoGames => {
+            let site_config = &config.crypto_games;
+            if crypto_games::Currency::try_from(&site_config.currency).is_err() {
+                return Err(ConfigError::UnsupportedCurrency {
+                    site: Sites::CryptoGames,
+                    currency: site_config.currency.clone(),
+                });
+            }
+
+            (
+                Box::new(
+                    crypto_games::CryptoGames::default()
+                        .with_api_key(site_config.api_key.clone())
+                        .with_currency(site_config.currency.clone()),
+                ),
+                ResetPolicy::default(),
+            )
+        }
+        Sites::FreeBitcoIn => {
+            let site_config = &config.freebitcoin;
+
+            (
+                Box::new(
+                    free_bitco_in::FreeBitcoIn::default()
+                        .with_username(site_config.btc_address.clone())
+                        .with_password(site_config.password.clone()),
+                ),
+                ResetPolicy::default(),
+            )
+        }
+        Sites::DuckDiceIo => {
+            let site_config = &config.duck_dice;
+
+            (
+                Box::new(
+                    duck_dice::DuckDiceIo::default()
+                        .with_api_key(site_config.api_key.clone())
+                        .with_currency(site_config.currency.clone()),
+                ),
+                ResetPolicy {
+                    win_multiple: Some(10.),
+                    reset_on_bust: true,
+                },
+            )
+        }
+        Sites::Windice => {
+            let site_config = &config.windice;
+            if windice::Currency::try_from(&site_config.currency).is_err() {
+                return Err(ConfigError::UnsupportedCurrency {
+                    site: Sites::Windice,
+                    currency: site_config.currency.clone(),
+                });
+            }
+
+            (
+                Box::new(
+                    windice::Windice::default()
+                        .with_api_key(site_config.api_key.clone())
+                        .with_currency(site_config.currency.clone()),
+                ),
+                ResetPolicy::default(),
+            )
+        }
+    };
+
+    if paper {
+        client = Box::new(paper::PaperSite::new(client));
+    }
+
+    let (strategy, params) = config.strategy(active_site);
+    let min_bet = client.capabilities().min_bet;
+    let mut site = BettingSession::new(client, strategies::from_toml(strategy, min_bet))
+        .with_reset_policy(reset_policy);
+    site.set_strategy_params(params.clone())
+        .map_err(|source| ConfigError::StrategyParams {
+            site: active_site,
+            strategy,
+            source,
+        })?;
+
+    Ok(Box::new(site))
 }
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::currency::Currency;
+use crate::sites::provably_fair::{roll_algorithm, DuckDiceRoll, RollAlgorithm};
+use crate::sites::{
+    random_seed, BetDecision, BetError, BetKind, BetOutcome, CurrencyBalance, SeedInfo, SeedMap,
+    SiteCapabilities, SiteClient, Sites,
+};
+
+/// How paper bets on `site` are rolled and committed to. Windice's verifier isn't implemented,
+/// its paper bets roll like DuckDice's.
+fn algorithm(site: Sites) -> &'static dyn RollAlgorithm {
+    roll_algorithm(site).unwrap_or(&DuckDiceRoll)
+}
+
+/// The local seed pair paper bets are rolled from.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct PaperSeeds {
+    server_seed: String,
+    server_seed_hash: String,
+    previous_hash: String,
+    client_seed: String,
+    nonce: u64,
+    /// Server seeds of the retired pairs.
+    #[serde(default)]
+    revealed: SeedMap,
+}
+
+impl PaperSeeds {
+    fn new(algorithm: &dyn RollAlgorithm) -> Self {
+        let mut seeds = Self {
+            server_seed: String::new(),
+            server_seed_hash: String::new(),
+            previous_hash: String::new(),
+            client_seed: random_seed(30),
+            nonce: 0,
+            revealed: SeedMap::default(),
+        };
+        seeds.rotate_server_seed(algorithm);
+
+        seeds
+    }
+
+    fn rotate_server_seed(&mut self, algorithm: &dyn RollAlgorithm) {
+        let server_seed = std::mem::replace(&mut self.server_seed, random_seed(64));
+        self.revealed
+            .insert(self.server_seed_hash.clone(), server_seed);
+        self.previous_hash = std::mem::take(&mut self.server_seed_hash);
+        self.server_seed_hash = algorithm.server_seed_hash(&self.server_seed);
+        self.nonce = 0;
+    }
+
+    fn rotate(&mut self, algorithm: &dyn RollAlgorithm) {
+        self.client_seed = random_seed(30);
+        self.rotate_server_seed(algorithm);
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct PaperState {
+    balance: f32,
+    seeds: PaperSeeds,
+    #[serde(default)]
+    parked: HashMap<String, f32>,
+    client: serde_json::Value,
+}
+
+/// Paper trades any site: the wrapped client logs in and reports the starting balance, every
+/// bet is then settled locally with the site's provably-fair roll against a virtual balance.
+pub struct PaperSite {
+    client: Box<dyn SiteClient>,
+    balance: f32,
+    seeds: PaperSeeds,
+    /// Virtual balances of the currencies switched away from, by symbol.
+    parked: HashMap<String, f32>,
+}
+
+impl PaperSite {
+    pub fn new(client: Box<dyn SiteClient>) -> Self {
+        let seeds = PaperSeeds::new(algorithm(client.site()));
+
+        Self {
+            client,
+            balance: 0.,
+            seeds,
+            parked: HashMap::new(),
+        }
+    }
+
+    /// The real site balance of the wrapped client's currency.
+    async fn fetch_site_balance(&mut self) -> Result<f32, BetError> {
+        let symbol = self.client.symbol();
+
+        Ok(self
+            .client
+            .fetch_balances()
+            .await?
+            .into_iter()
+            .find(|balance| balance.symbol == symbol)
+            .map_or(0., |balance| balance.amount))
+    }
+
+    fn roll(&mut self) -> u32 {
+        let algorithm = algorithm(self.client.site());
+        let seeds = &mut self.seeds;
+        let number = algorithm.roll(&seeds.server_seed, &seeds.client_seed, seeds.nonce);
+        seeds.nonce += 1;
+
+        number
+    }
+}
+
+#[async_trait]
+impl SiteClient for PaperSite {
+    fn site(&self) -> Sites {
+        self.client.site()
+    }
+
+    fn symbol(&self) -> String {
+        self.client.symbol()
+    }
+
+    fn capabilities(&self) -> SiteCapabilities {
+        self.client.capabilities()
+    }
+
+    fn odds(&self, chance: f32, multiplier: f32) -> (f32, f32) {
+        self.client.odds(chance, multiplier)
+    }
+
+    /// Logs into the real site for its balance, nothing is ever wagered there.
+    async fn login(&mut self) -> Result<(), BetError> {
+        self.client.login().await?;
+        self.balance = self.fetch_site_balance().await?;
+
+        Ok(())
+    }
+
+    /// Settles whichever kinds the wrapped site takes.
+    async fn place_bet(&mut self, wager: &BetDecision) -> Result<BetOutcome, BetError> {
+        if !self.client.capabilities().supports(&wager.kind) {
+            return Err(BetError::UnsupportedBetKind {
+                site: self.client.site(),
+                kind: wager.kind,
+            });
+        }
+        if wager.amount > self.balance {
+            return Err(BetError::InsufficientBalance {
+                needed: wager.amount,
+                available: self.balance,
+            });
+        }
+
+        let client_seed = self.seeds.client_seed.clone();
+        let nonce = self.seeds.nonce;
+        let number = self.roll();
+        // The hashes are recorded the way the site's replies carry them, so `verify` audits
+        // paper ledgers like live ones.
+        let (hash_previous_roll, hash_next_roll) = match self.client.site() {
+            // crypto.games reveals the server seed after every bet.
+            Sites::CryptoGames => {
+                let server_seed = self.seeds.server_seed.clone();
+                self.seeds.rotate_server_seed(algorithm(self.client.site()));
+
+                (server_seed, self.seeds.server_seed_hash.clone())
+            }
+            Sites::FreeBitcoIn => (
+                self.seeds.server_seed_hash.clone(),
+                self.seeds.server_seed_hash.clone(),
+            ),
+            Sites::DuckDiceIo | Sites::Windice => (
+                self.seeds.previous_hash.clone(),
+                self.seeds.server_seed_hash.clone(),
+            ),
+        };
+
+        let site = self.client.site();
+        let result = wager.kind.wins(number, wager.chance, site.max_roll());
+        let win_amount = if result {
+            wager.amount * (wager.multiplier - 1.)
+        } else {
+            -wager.amount
+        };
+        self.balance += win_amount;
+
+        Ok(BetOutcome {
+            bet_id: String::new(),
+            hash_previous_roll,
+            hash_next_roll,
+            client_seed,
+            nonce: nonce as u32,
+            symbol: self.client.symbol(),
+            result,
+            is_high: wager.kind == BetKind::Over,
+            kind: wager.kind,
+            number,
+            threshold: wager.threshold(site),
+            chance: wager.chance,
+            payout: wager.multiplier,
+            bet_amount: wager.amount,
+            win_amount,
+            timestamp: 0,
+        }
+        .complete(wager, site))
+    }
+
+    /// The virtual balances, of the session's currency and the ones switched away from.
+    async fn fetch_balances(&mut self) -> Result<Vec<CurrencyBalance>, BetError> {
+        let mut balances = vec![CurrencyBalance {
+            symbol: self.client.symbol(),
+            amount: self.balance,
+        }];
+        balances.extend(self.parked.iter().map(|(symbol, amount)| CurrencyBalance {
+            symbol: symbol.clone(),
+            amount: *amount,
+        }));
+
+        Ok(balances)
+    }
+
+    /// Parks the virtual balance, a currency never switched to before starts from the real one.
+    async fn switch_currency(&mut self, currency: &Currency) -> Result<(), BetError> {
+        let from = self.client.symbol();
+        self.client.switch_currency(currency).await?;
+        let to = self.client.symbol();
+        if to == from {
+            return Ok(());
+        }
+
+        let balance = match self.parked.remove(&to) {
+            Some(balance) => balance,
+            None => match self.fetch_site_balance().await {
+                Ok(balance) => balance,
+                Err(err) => {
+                    if let Ok(currency) = from.parse::<Currency>() {
+                        self.client.switch_currency(&currency).await?;
+                    }
+                    return Err(err);
+                }
+            },
+        };
+        self.parked
+            .insert(from, std::mem::replace(&mut self.balance, balance));
+
+        Ok(())
+    }
+
+    async fn rotate_seed(&mut self) -> Result<(), BetError> {
+        self.seeds.rotate(algorithm(self.client.site()));
+
+        Ok(())
+    }
+
+    fn seed_info(&self) -> SeedInfo {
+        SeedInfo {
+            server_seed_hash: self.seeds.server_seed_hash.clone(),
+            client_seed: self.seeds.client_seed.clone(),
+            nonce: self.seeds.nonce,
+        }
+    }
+
+    async fn fetch_revealed_seed(&mut self, server_seed_hash: &str) -> Result<String, BetError> {
+        self.seeds
+            .revealed
+            .get(server_seed_hash)
+            .map(str::to_string)
+            .ok_or_else(|| BetError::SeedUnavailable {
+                server_seed_hash: server_seed_hash.to_string(),
+            })
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(PaperState {
+            balance: self.balance,
+            seeds: self.seeds.clone(),
+            parked: self.parked.clone(),
+            client: self.client.save_state(),
+        })
+        .unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        let state: PaperState = serde_json::from_value(state)?;
+        self.client.load_state(state.client)?;
+
+        self.balance = state.balance;
+        self.seeds = state.seeds;
+        self.parked = state.parked;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use ring::hmac;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::sites::Sites;
+
+/// How a site turns a seed pair and nonce into a roll, shared by paper bets, the training data
+/// and `verify`.
+pub trait RollAlgorithm: Send + Sync {
+    fn roll(&self, server_seed: &str, client_seed: &str, nonce: u64) -> u32;
+
+    /// The commitment a site publishes before the server seed is revealed.
+    fn server_seed_hash(&self, server_seed: &str) -> String {
+        hex::encode(Sha256::digest(server_seed.as_bytes()))
+    }
+}
+
+/// The first five hex digit window of `hash` under a million, `None` when there is none.
+fn lucky_number(hash: &str) -> Option<u32> {
+    (0..hash.len() / 5)
+        .filter_map(|index| u32::from_str_radix(&hash[index * 5..index * 5 + 5], 16).ok())
+        .find(|lucky| *lucky < 1000000)
+}
+
+/// DuckdiceIO dice generation, returns a roll in `0..10000`. A hash without a window under a
+/// million rolls 9999, the final step of the site's verifier.
+pub struct DuckDiceRoll;
+
+impl RollAlgorithm for DuckDiceRoll {
+    fn roll(&self, server_seed: &str, client_seed: &str, nonce: u64) -> u32 {
+        let mut combined_seed = Vec::new();
+        combined_seed.extend_from_slice(server_seed.as_bytes());
+        combined_seed.extend_from_slice(client_seed.as_bytes());
+        combined_seed.extend_from_slice(nonce.to_string().as_bytes());
+
+        let mut hasher = Sha512::new();
+        hasher.update(combined_seed);
+        let result = hasher.finalize();
+        let hash = hex::encode(result);
+
+        lucky_number(&hash).map_or(9999, |lucky| lucky % 10000)
+    }
+}
+
+/// freebitco.in dice generation, returns a roll in `0..=10000`.
+pub struct FreeBitcoInRoll;
+
+impl RollAlgorithm for FreeBitcoInRoll {
+    fn roll(&self, server_seed: &str, client_seed: &str, nonce: u64) -> u32 {
+        let key = hmac::Key::new(
+            hmac::HMAC_SHA512,
+            format!("{nonce}:{server_seed}:{nonce}").as_bytes(),
+        );
+        let tag = hmac::sign(&key, format!("{nonce}:{client_seed}:{nonce}").as_bytes());
+        let hash = hex::encode(tag.as_ref());
+        let lucky = u32::from_str_radix(&hash[..8], 16).unwrap();
+
+        // The site rounds, its verifier is `Math.round(lucky / 429496.7295)`.
+        (lucky as f64 / 429496.7295).round() as u32
+    }
+}
+
+/// crypto.games dice generation, the server seed changes every bet so the nonce is ignored.
+/// The site shows `lucky % 100000 / 1000` to three decimals, this returns it in hundredths,
+/// `0..10000`, dropping the third. Like DuckDice, a hash without a window under a million rolls
+/// 9999.
+pub struct CryptoGamesRoll;
+
+impl RollAlgorithm for CryptoGamesRoll {
+    fn roll(&self, server_seed: &str, client_seed: &str, _nonce: u64) -> u32 {
+        let hash = hex::encode(Sha512::digest(format!("{server_seed}{client_seed}")));
+
+        lucky_number(&hash).map_or(9999, |lucky| lucky % 100000 / 10)
+    }
+}
+
+/// The roll algorithm of `site`, `None` for Windice whose verifier isn't implemented.
+pub fn roll_algorithm(site: Sites) -> Option<&'static dyn RollAlgorithm> {
+    match site {
+        Sites::DuckDiceIo => Some(&DuckDiceRoll),
+        Sites::FreeBitcoIn => Some(&FreeBitcoInRoll),
+        Sites::CryptoGames => Some(&CryptoGamesRoll),
+        Sites::Windice => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Regression values, not the examples from the sites' fairness pages. Each algorithm follows
+    // its site's published formula: DuckDice's first five hex digits of the SHA-512 under a
+    // million, freebitco.in's `Math.round` of the HMAC's first eight hex digits over 429496.7295
+    // and crypto.games' lucky number `% 100000` in thousandths.
+    const VECTORS: [(&str, &str, u64); 3] = [
+        ("a3f1c9e07b5d42e8", "lYypIPVEgzvCflWF", 0),
+        ("a3f1c9e07b5d42e8", "lYypIPVEgzvCflWF", 1),
+        ("9b2e6d4410fc7a35", "client", 1337),
+    ];
+
+    fn rolls(algorithm: &dyn RollAlgorithm) -> Vec<u32> {
+        VECTORS
+            .iter()
+            .map(|(server_seed, client_seed, nonce)| {
+                algorithm.roll(server_seed, client_seed, *nonce)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rolls_match_the_reference_vectors() {
+        assert_eq!(rolls(&DuckDiceRoll), [5656, 2182, 6885]);
+        assert_eq!(rolls(&FreeBitcoInRoll), [1404, 6791, 7138]);
+        assert_eq!(rolls(&CryptoGamesRoll), [2736, 2736, 2367]);
+        assert_eq!(lucky_number(&format!("{}0f4", "f".repeat(125))), None);
+        assert_eq!(
+            lucky_number(&format!("ffffff423f{}", "f".repeat(118))),
+            Some(0xf423f)
+        );
+        assert_eq!(
+            DuckDiceRoll.server_seed_hash("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
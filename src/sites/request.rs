@@ -0,0 +1,337 @@
+//! The request layer every site client sends through: a token bucket per site, shared by all
+//! of its sessions, and retries of transient failures with backoff. It owns the retries of each
+//! request, `game::Game` only backs off between bets once they're exhausted.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use rand::Rng;
+use tokio::time::Instant;
+
+use crate::sites::{check_status, BetError, Sites};
+
+lazy_static! {
+    static ref BUCKETS: Mutex<HashMap<Sites, Arc<Mutex<TokenBucket>>>> = Mutex::new(HashMap::new());
+}
+
+/// Refills `rate` tokens a second up to `rate`, a request takes one.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        Self {
+            rate,
+            tokens: rate,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Takes a token, or returns how long until the next one is available.
+    fn take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled_at = now;
+
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1. - self.tokens) / self.rate))
+        }
+    }
+}
+
+/// How often and how patiently a failed request is retried.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Attempts per request, the first one included.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff before attempt `attempt + 1`, jittered into its upper half so
+    /// sessions on the same site don't retry in lockstep.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+
+        backoff.mul_f64(rand::rng().random_range(0.5..=1.))
+    }
+}
+
+/// Sends a site's requests through its token bucket and retries transient failures.
+#[derive(Clone, Debug)]
+pub struct Requester {
+    bucket: Arc<Mutex<TokenBucket>>,
+    policy: RetryPolicy,
+}
+
+impl Requester {
+    pub fn new(site: Sites) -> Self {
+        let bucket = BUCKETS
+            .lock()
+            .unwrap()
+            .entry(site)
+            .or_insert_with(|| Arc::new(Mutex::new(TokenBucket::new(site.requests_per_second()))))
+            .clone();
+
+        Self {
+            bucket,
+            policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Gives the requester a bucket of its own instead of the site's shared one.
+    pub fn with_rate(mut self, requests_per_second: f64) -> Self {
+        self.bucket = Arc::new(Mutex::new(TokenBucket::new(requests_per_second)));
+
+        self
+    }
+
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+
+        self
+    }
+
+    /// Sends a request that's safe to repeat, retrying rate limits, server errors, timeouts
+    /// and connection failures.
+    pub async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, BetError> {
+        self.send_with(request, |err| match err {
+            BetError::RateLimited { .. } => true,
+            BetError::HttpStatus(status) => status.is_server_error(),
+            BetError::ReqwestError(source) => source.is_connect() || source.is_timeout(),
+            _ => false,
+        })
+        .await
+    }
+
+    /// Sends a bet, only retried when the site can't have taken it: rate limits and failed
+    /// connections. Other failures are left to the session, which decides on a new bet.
+    pub async fn send_bet(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, BetError> {
+        self.send_with(request, |err| match err {
+            BetError::RateLimited { .. } => true,
+            BetError::ReqwestError(source) => source.is_connect(),
+            _ => false,
+        })
+        .await
+    }
+
+    async fn send_with(
+        &self,
+        request: reqwest::RequestBuilder,
+        retryable: impl Fn(&BetError) -> bool,
+    ) -> Result<reqwest::Response, BetError> {
+        let mut attempt = 1;
+        loop {
+            // Streamed bodies can't be cloned, those requests get a single attempt.
+            let Some(retry) = request.try_clone() else {
+                self.acquire().await;
+                return check_status(request.send().await?).await;
+            };
+
+            self.acquire().await;
+            let err = match retry.send().await {
+                Ok(res) => match check_status(res).await {
+                    Ok(res) => return Ok(res),
+                    Err(err) => err,
+                },
+                Err(err) => err.into(),
+            };
+
+            if !retryable(&err) {
+                return Err(err);
+            }
+
+            let delay = match &err {
+                BetError::RateLimited {
+                    retry_after: Some(retry_after),
+                } => *retry_after,
+                _ => self.policy.backoff(attempt),
+            };
+            // Waiting out a `Retry-After` past `max_delay` is left to the caller.
+            if attempt >= self.policy.max_attempts || delay > self.policy.max_delay {
+                return Err(BetError::RetriesExhausted {
+                    attempts: attempt,
+                    last: Box::new(err),
+                });
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn acquire(&self) {
+        loop {
+            let wait = match self.bucket.lock().unwrap().take() {
+                Ok(()) => return,
+                Err(wait) => wait,
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::sites::mock::{MockServer, Request, Response};
+
+    fn requester() -> Requester {
+        Requester::new(Sites::Windice)
+            .with_rate(1000.)
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_millis(50),
+            })
+    }
+
+    /// Fails with `status` until `failures` requests were served.
+    async fn flaky(status: u16, failures: u32) -> MockServer {
+        let served = AtomicU32::new(0);
+
+        MockServer::start(move |_: &Request| {
+            if served.fetch_add(1, Ordering::SeqCst) < failures {
+                Response::json(status, json!({ "message": "busy" })).with_header("Retry-After", "0")
+            } else {
+                Response::json(200, json!({ "ok": true }))
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn rate_limits_are_retried() {
+        let server = flaky(429, 2).await;
+        let client = reqwest::Client::new();
+
+        let res = requester()
+            .send(client.get(&server.base_url))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let server = flaky(503, u32::MAX).await;
+        let client = reqwest::Client::new();
+
+        let err = requester()
+            .send(client.get(&server.base_url))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BetError::RetriesExhausted { attempts: 3, last } if matches!(*last, BetError::HttpStatus(_))
+        ));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn bets_are_not_repeated_after_server_errors() {
+        let server = flaky(500, 1).await;
+        let client = reqwest::Client::new();
+
+        let err = requester()
+            .send_bet(client.post(&server.base_url))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BetError::HttpStatus(_)));
+        assert_eq!(server.requests().len(), 1);
+
+        let res = requester().send_bet(client.post(&server.base_url)).await;
+        assert!(res.is_ok());
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_honored() {
+        let served = AtomicU32::new(0);
+        let server = MockServer::start(move |_: &Request| {
+            if served.fetch_add(1, Ordering::SeqCst) == 0 {
+                Response::json(429, json!({ "message": "busy" })).with_header("Retry-After", "1")
+            } else {
+                Response::json(200, json!({ "ok": true }))
+            }
+        })
+        .await;
+        let client = reqwest::Client::new();
+
+        let sent = Instant::now();
+        let res = requester()
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_secs(2),
+            })
+            .send(client.get(&server.base_url))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        assert!(sent.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn retry_after_past_max_delay_gives_up_at_once() {
+        let server = MockServer::start(|_: &Request| {
+            Response::json(429, json!({ "message": "busy" })).with_header("Retry-After", "3600")
+        })
+        .await;
+        let client = reqwest::Client::new();
+
+        let err = requester()
+            .send(client.get(&server.base_url))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BetError::RetriesExhausted { attempts: 1, last }
+                if matches!(*last, BetError::RateLimited { retry_after: Some(retry_after) } if retry_after == Duration::from_secs(3600))
+        ));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn token_bucket_spaces_out_requests() {
+        let mut bucket = TokenBucket::new(2.);
+        assert!(bucket.take().is_ok());
+        assert!(bucket.take().is_ok());
+
+        let wait = bucket.take().unwrap_err();
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::sites::audit::commitment;
+use crate::sites::ledger::{bet_key, Rotation};
+use crate::sites::provably_fair::roll_algorithm;
+use crate::sites::{BetOutcome, Sites};
+
+/// A seed pair from its commitment to its reveal, with the bets placed under it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeedPair {
+    /// The server seed hash, empty when the ledger doesn't hold it.
+    pub commitment: String,
+    pub client_seed: String,
+    /// First and last nonce bet.
+    pub nonces: (u32, u32),
+    /// Ledger indices of the pair's bets.
+    pub bets: Vec<usize>,
+    /// Whether a rotation or, on sites with a server seed per bet, a later bet ended the pair.
+    pub retired: bool,
+    pub server_seed: Option<String>,
+}
+
+/// Something in the ledger its seed pairs don't account for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChainIssue {
+    /// Nonces skipped within a pair, bets the site took that never reached the ledger.
+    NonceGap {
+        bet: String,
+        expected: u32,
+        found: u32,
+    },
+    /// The server seed hash or client seed changed with no rotation in between.
+    UnexpectedChange {
+        bet: String,
+        field: &'static str,
+        from: String,
+        to: String,
+    },
+    /// The revealed server seed doesn't hash to the pair's commitment.
+    RevealMismatch {
+        commitment: String,
+        server_seed_hash: String,
+    },
+}
+
+impl fmt::Display for ChainIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonceGap {
+                bet,
+                expected,
+                found,
+            } => write!(f, "{bet}: nonce {found}, expected {expected}"),
+            Self::UnexpectedChange {
+                bet,
+                field,
+                from,
+                to,
+            } => write!(
+                f,
+                "{bet}: {field} changed from {from:?} to {to:?} without a rotation"
+            ),
+            Self::RevealMismatch {
+                commitment,
+                server_seed_hash,
+            } => write!(
+                f,
+                "pair {commitment}: revealed server seed hashes to {server_seed_hash}"
+            ),
+        }
+    }
+}
+
+/// The seed pairs of a ledger in order and what doesn't line up between them.
+#[derive(Clone, Debug, Default)]
+pub struct SeedChain {
+    pub pairs: Vec<SeedPair>,
+    pub issues: Vec<ChainIssue>,
+}
+
+impl SeedChain {
+    /// Splits `bets` into pairs at each rotation and each commitment or client seed change,
+    /// checking that only rotations change them, that nonces run on within a pair and that
+    /// reveals, from `rotations` or carried by the bets, match their commitments.
+    pub fn build(site: Sites, bets: &[BetOutcome], rotations: &[Rotation]) -> Self {
+        let rotated_at = rotations
+            .iter()
+            .map(|rotation| rotation.bets)
+            .collect::<HashSet<usize>>();
+        let revealed = rotations
+            .iter()
+            .flat_map(|rotation| rotation.revealed.iter())
+            .map(|(hash, server_seed)| (hash.as_str(), server_seed.as_str()))
+            .collect::<HashMap<&str, &str>>();
+        let mut chain = Self::default();
+
+        for (index, bet) in bets.iter().enumerate() {
+            let (committed, carried) = commitment(site, bets, index);
+            let committed = committed.unwrap_or_default();
+            let rotated = rotated_at.contains(&index);
+
+            if let Some(previous) = index.checked_sub(1).map(|previous| &bets[previous]) {
+                if !rotated {
+                    chain.check_continuity(site, previous, bet, committed);
+                }
+            }
+
+            match chain.pairs.last_mut() {
+                Some(pair)
+                    if !rotated
+                        && pair.commitment == committed
+                        && pair.client_seed == bet.client_seed =>
+                {
+                    pair.nonces.1 = bet.nonce;
+                    pair.bets.push(index);
+                }
+                last => {
+                    if let Some(pair) = last {
+                        pair.retired = true;
+                    }
+                    chain.pairs.push(SeedPair {
+                        commitment: committed.to_string(),
+                        client_seed: bet.client_seed.clone(),
+                        nonces: (bet.nonce, bet.nonce),
+                        bets: vec![index],
+                        retired: false,
+                        server_seed: carried
+                            .or_else(|| revealed.get(committed).copied())
+                            .map(str::to_string),
+                    });
+                }
+            }
+        }
+        if let Some(pair) = chain.pairs.last_mut() {
+            let next = pair.bets.last().map_or(0, |last| last + 1);
+            pair.retired = rotations.iter().any(|rotation| rotation.bets >= next);
+        }
+
+        // Without the site's algorithm reveals can't be checked.
+        let Some(algorithm) = roll_algorithm(site) else {
+            return chain;
+        };
+        for pair in &chain.pairs {
+            let Some(server_seed) = pair.server_seed.as_deref() else {
+                continue;
+            };
+            let server_seed_hash = algorithm.server_seed_hash(server_seed);
+            if !pair.commitment.is_empty() && server_seed_hash != pair.commitment {
+                chain.issues.push(ChainIssue::RevealMismatch {
+                    commitment: pair.commitment.clone(),
+                    server_seed_hash,
+                });
+            }
+        }
+
+        chain
+    }
+
+    /// Checks `bet` follows on from `previous` with no rotation in between.
+    fn check_continuity(
+        &mut self,
+        site: Sites,
+        previous: &BetOutcome,
+        bet: &BetOutcome,
+        committed: &str,
+    ) {
+        let mut change = |field, from: &str, to: &str| {
+            self.issues.push(ChainIssue::UnexpectedChange {
+                bet: bet_key(bet),
+                field,
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+        };
+
+        if previous.client_seed != bet.client_seed {
+            change("client seed", &previous.client_seed, &bet.client_seed);
+        }
+        // Every bet announces the commitment of the next, crypto.games bets only carry that.
+        if site != Sites::CryptoGames && previous.hash_next_roll != committed {
+            change("server seed hash", &previous.hash_next_roll, committed);
+        }
+        // crypto.games rolls ignore the nonce.
+        if site != Sites::CryptoGames
+            && previous.client_seed == bet.client_seed
+            && bet.nonce != previous.nonce + 1
+        {
+            self.issues.push(ChainIssue::NonceGap {
+                bet: bet_key(bet),
+                expected: previous.nonce + 1,
+                found: bet.nonce,
+            });
+        }
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for SeedChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let retired = self.pairs.iter().filter(|pair| pair.retired).count();
+        let revealed = self
+            .pairs
+            .iter()
+            .filter(|pair| pair.server_seed.is_some())
+            .count();
+
+        write!(
+            f,
+            "{} seed pairs: {retired} retired, {revealed} revealed, {} issue(s)",
+            self.pairs.len(),
+            self.issues.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sites::provably_fair::{DuckDiceRoll, RollAlgorithm};
+    use crate::sites::{BetKind, SeedInfo};
+
+    fn bet(server_seed: &str, client_seed: &str, nonce: u32) -> BetOutcome {
+        BetOutcome {
+            bet_id: format!("{server_seed}-{nonce}"),
+            hash_previous_roll: String::new(),
+            hash_next_roll: DuckDiceRoll.server_seed_hash(server_seed),
+            client_seed: client_seed.to_string(),
+            nonce,
+            symbol: "BTC".to_string(),
+            result: false,
+            is_high: true,
+            kind: BetKind::Over,
+            number: 0,
+            threshold: 0,
+            chance: 49.5,
+            payout: 2.,
+            bet_amount: 0.001,
+            win_amount: -0.001,
+            timestamp: 0,
+        }
+    }
+
+    fn rotation(bets: usize, server_seed: &str, revealed: &str) -> Rotation {
+        let server_seed_hash = DuckDiceRoll.server_seed_hash(server_seed);
+
+        Rotation {
+            bets,
+            retired: SeedInfo {
+                server_seed_hash: server_seed_hash.clone(),
+                client_seed: String::new(),
+                nonce: 0,
+            },
+            revealed: vec![(server_seed_hash, revealed.to_string())],
+        }
+    }
+
+    #[test]
+    fn pairs_follow_rotations_only() {
+        let bets = [
+            bet("first", "a", 0),
+            bet("first", "a", 1),
+            bet("first", "a", 3),
+            bet("second", "b", 0),
+            bet("second", "b", 1),
+            bet("third", "b", 2),
+        ];
+        let rotations = [rotation(3, "first", "first"), rotation(5, "second", "liar")];
+
+        let chain = SeedChain::build(Sites::DuckDiceIo, &bets, &rotations);
+        assert_eq!(chain.pairs.len(), 3);
+        assert_eq!(chain.pairs[0].nonces, (0, 3));
+        assert_eq!(chain.pairs[0].server_seed.as_deref(), Some("first"));
+        assert!(chain.pairs[1].retired && !chain.pairs[2].retired);
+        assert_eq!(
+            chain.issues,
+            vec![
+                ChainIssue::NonceGap {
+                    bet: "first-3".to_string(),
+                    expected: 2,
+                    found: 3,
+                },
+                ChainIssue::RevealMismatch {
+                    commitment: DuckDiceRoll.server_seed_hash("second"),
+                    server_seed_hash: DuckDiceRoll.server_seed_hash("liar"),
+                },
+            ]
+        );
+
+        let rotations = [rotation(3, "first", "first")];
+        let chain = SeedChain::build(Sites::DuckDiceIo, &bets, &rotations);
+        assert!(matches!(
+            chain.issues[1],
+            ChainIssue::UnexpectedChange {
+                field: "server seed hash",
+                ..
+            }
+        ));
+    }
+}
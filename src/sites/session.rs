@@ -0,0 +1,555 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::currency::Currency;
+use crate::sites::audit::{self, Audit, PairAudit};
+use crate::sites::ledger::{Ledger, Reconciliation};
+use crate::sites::{
+    BetDecision, BetError, BetKind, BetOutcome, CurrencyBalance, SeedInfo, Site, SiteCapabilities,
+    SiteClient,
+};
+use crate::strategies::Strategy;
+
+/// Bets placed at the minimum before the strategy takes over, they fill the model's history.
+const HISTORY_SIZE: usize = 10;
+/// Bets looked up on the site per reconciliation, the newest ones.
+const RECONCILE_LOOKUPS: usize = 20;
+
+/// When a session starts over from the site balance.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResetPolicy {
+    /// Start over once the balance reaches this multiple of the starting balance.
+    pub win_multiple: Option<f32>,
+    /// Start over on a fresh seed pair instead of failing when the balance can't cover a bet.
+    pub reset_on_bust: bool,
+}
+
+/// The accounting of a currency the session switched away from, restored when it comes back.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct CurrencyBook {
+    history: Vec<BetOutcome>,
+    balance: f32,
+    initial_balance: f32,
+    site_balance: f32,
+    profit: f32,
+    wins: u64,
+    losses: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct SessionState {
+    rolls: u64,
+    history: Vec<BetOutcome>,
+    current_bet: f32,
+    chance: f32,
+    multiplier: f32,
+    balance: f32,
+    initial_balance: f32,
+    profit: f32,
+    wins: u64,
+    losses: u64,
+    strategy: serde_json::Value,
+    client: serde_json::Value,
+    #[serde(default)]
+    ledger: Ledger,
+    /// Empty in states saved before sessions could switch currencies.
+    #[serde(default)]
+    symbol: String,
+    #[serde(default)]
+    parked: HashMap<String, CurrencyBook>,
+}
+
+/// Runs a strategy against any `SiteClient`, keeping the history, the balance and profit
+/// accounting and the reset policy in one place.
+pub struct BettingSession {
+    client: Box<dyn SiteClient>,
+    strategy: Box<dyn Strategy>,
+    reset_policy: ResetPolicy,
+    history: Vec<BetOutcome>,
+    history_size: usize,
+    rolls: u64,
+    current_bet: f32,
+    chance: f32,
+    multiplier: f32,
+    balance: f32,
+    initial_balance: f32,
+    site_balance: f32,
+    profit: f32,
+    wins: u64,
+    losses: u64,
+    ledger: Ledger,
+    /// Books of the other currencies the session bet in, by symbol.
+    parked: HashMap<String, CurrencyBook>,
+    /// Audits of retired seed pairs still running, see `Site::rotate_seed`.
+    audits: Vec<(SeedInfo, usize, JoinHandle<Audit>)>,
+}
+
+impl BettingSession {
+    pub fn new(client: Box<dyn SiteClient>, strategy: Box<dyn Strategy>) -> Self {
+        let min_bet = client.capabilities().min_bet;
+        let ledger = Ledger::new(client.site());
+
+        Self {
+            client,
+            strategy,
+            reset_policy: ResetPolicy::default(),
+            history: Vec::new(),
+            history_size: HISTORY_SIZE,
+            rolls: 0,
+            current_bet: min_bet,
+            chance: 50.,
+            multiplier: 2.,
+            balance: 0.,
+            initial_balance: 0.,
+            site_balance: 0.,
+            profit: 0.,
+            wins: 0,
+            losses: 0,
+            ledger,
+            parked: HashMap::new(),
+            audits: Vec::new(),
+        }
+    }
+
+    pub fn with_reset_policy(mut self, reset_policy: ResetPolicy) -> Self {
+        self.reset_policy = reset_policy;
+
+        self
+    }
+
+    fn warming_up(&self) -> bool {
+        self.history.len() < self.history_size
+    }
+
+    /// The site's balance in the session's currency.
+    async fn fetch_site_balance(&mut self) -> Result<f32, BetError> {
+        let symbol = self.client.symbol();
+
+        Ok(self
+            .client
+            .fetch_balances()
+            .await?
+            .into_iter()
+            .find(|balance| balance.symbol == symbol)
+            .map_or(0., |balance| balance.amount))
+    }
+
+    /// Refetches the balance of the session's currency and starts the accounting over from it.
+    async fn refresh_balance(&mut self) -> Result<(), BetError> {
+        self.site_balance = self.fetch_site_balance().await?;
+        self.start_over();
+
+        Ok(())
+    }
+
+    fn take_book(&mut self) -> CurrencyBook {
+        CurrencyBook {
+            history: std::mem::take(&mut self.history),
+            balance: self.balance,
+            initial_balance: self.initial_balance,
+            site_balance: self.site_balance,
+            profit: self.profit,
+            wins: self.wins,
+            losses: self.losses,
+        }
+    }
+
+    fn put_book(&mut self, book: CurrencyBook) {
+        self.history = book.history;
+        self.balance = book.balance;
+        self.initial_balance = book.initial_balance;
+        self.site_balance = book.site_balance;
+        self.profit = book.profit;
+        self.wins = book.wins;
+        self.losses = book.losses;
+    }
+
+    fn start_over(&mut self) {
+        self.balance = self.site_balance;
+        self.initial_balance = self.site_balance;
+        self.strategy.set_balance(self.site_balance);
+        self.strategy.reset();
+    }
+
+    fn pick(&mut self, prediction: f32, confidence: f32) -> BetDecision {
+        let decision = self.strategy.get_next_bet(prediction, confidence);
+        let capabilities = self.client.capabilities();
+
+        if self.warming_up() {
+            return BetDecision {
+                amount: capabilities.min_bet,
+                chance: capabilities.chance(2.),
+                multiplier: 2.,
+                // Warm-up bets pay 2x, a range would pin another chance.
+                kind: if decision.kind.is_range() {
+                    BetKind::from_high(prediction > 5000.)
+                } else {
+                    decision.kind
+                },
+            };
+        }
+
+        let (chance, multiplier) = match decision.kind.range_chance() {
+            Some(chance) => (chance, capabilities.multiplier(chance)),
+            None => self.client.odds(decision.chance, decision.multiplier),
+        };
+        capabilities.clamp(BetDecision {
+            chance,
+            multiplier,
+            ..decision
+        })
+    }
+
+    /// Applies the reset policy and picks the next bet, returns whether the seed pair has to be
+    /// rotated before placing it.
+    async fn next_wager(
+        &mut self,
+        prediction: f32,
+        confidence: f32,
+    ) -> Result<(BetDecision, bool), BetError> {
+        let mut rotate_seed = false;
+
+        if let Some(win_multiple) = self.reset_policy.win_multiple {
+            if self.balance >= self.initial_balance * win_multiple {
+                println!("[WIN] Resetting {:0>.8}", self.site_balance);
+                if self.profit > 0. {
+                    self.refresh_balance().await?;
+                    self.profit = 0.;
+                } else {
+                    self.start_over();
+                }
+            }
+        }
+
+        let mut wager = self.pick(prediction, confidence);
+        if wager.amount > self.balance && self.reset_policy.reset_on_bust {
+            println!("[FAIL] Resetting {:0>.8}", self.site_balance);
+            self.wins = 0;
+            self.losses = 0;
+            self.start_over();
+            rotate_seed = true;
+
+            wager = self.pick(prediction, confidence);
+        }
+        if wager.amount > self.balance {
+            return Err(BetError::InsufficientBalance {
+                needed: wager.amount,
+                available: self.balance,
+            });
+        }
+
+        self.current_bet = wager.amount;
+        self.chance = wager.chance;
+        self.multiplier = wager.multiplier;
+
+        Ok((wager, rotate_seed))
+    }
+
+    /// Records the finished pair audits in the ledger, or all of them once they finish with
+    /// `wait`. A failed one stops betting, one that died is recorded with its bets unverified.
+    async fn collect_audits(&mut self, wait: bool) -> Result<(), BetError> {
+        let (finished, running) = std::mem::take(&mut self.audits)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(_, _, handle)| wait || handle.is_finished());
+        self.audits = running;
+
+        let mut unfair = None;
+        for (retired, bets, handle) in finished {
+            let pair_audit = match handle.await {
+                Ok(audit) => PairAudit::new(&retired, &audit),
+                Err(err) => {
+                    let pair_audit = PairAudit::aborted(&retired, bets, err.to_string());
+                    eprintln!("{pair_audit}");
+                    pair_audit
+                }
+            };
+            if !pair_audit.is_clean() && unfair.is_none() {
+                unfair = Some(BetError::UnfairRolls {
+                    server_seed_hash: retired.server_seed_hash,
+                    failed: pair_audit.failures.len(),
+                });
+            }
+            self.ledger.record_audit(pair_audit);
+        }
+
+        unfair.map_or(Ok(()), Err)
+    }
+
+    fn push_history(&mut self, bet_result: BetOutcome) {
+        self.history.push(bet_result);
+        if self.history.len() > self.history_size {
+            self.history = self.history[1..].to_vec();
+        }
+    }
+}
+
+#[async_trait]
+impl Site for BettingSession {
+    async fn login(&mut self) -> Result<(), BetError> {
+        self.client.login().await?;
+        self.strategy.set_capabilities(&self.client.capabilities());
+        self.refresh_balance().await?;
+        let symbol = self.client.symbol();
+        self.ledger.open(&symbol, self.site_balance);
+        self.ledger
+            .record_house_edge(&symbol, self.client.capabilities().house_edge);
+
+        Ok(())
+    }
+
+    async fn reauthenticate(&mut self) -> Result<(), BetError> {
+        self.client.login().await?;
+        self.strategy.set_capabilities(&self.client.capabilities());
+        self.site_balance = self.fetch_site_balance().await?;
+
+        Ok(())
+    }
+
+    async fn do_bet(&mut self, prediction: f32, confidence: f32) -> Result<BetOutcome, BetError> {
+        self.collect_audits(false).await?;
+        let (wager, rotate_seed) = self.next_wager(prediction, confidence).await?;
+        if rotate_seed {
+            self.rotate_seed().await?;
+        }
+
+        let bet_result = self.client.place_bet(&wager).await?;
+        self.rolls += 1;
+        self.ledger.record(bet_result.clone());
+        self.push_history(bet_result.clone());
+
+        Ok(bet_result)
+    }
+
+    fn on_win(&mut self, bet_result: &BetOutcome) {
+        self.balance += bet_result.win_amount;
+        self.profit += bet_result.win_amount;
+        self.wins += 1;
+
+        if !self.warming_up() {
+            self.strategy.on_win(bet_result);
+        }
+    }
+
+    fn on_lose(&mut self, bet_result: &BetOutcome) {
+        self.balance += bet_result.win_amount;
+        self.profit += bet_result.win_amount;
+        self.losses += 1;
+
+        if !self.warming_up() {
+            // Strategies take the loss as a positive amount.
+            let mut bet_result = bet_result.clone();
+            bet_result.win_amount = -bet_result.win_amount;
+            self.strategy.on_lose(&bet_result);
+        }
+    }
+
+    fn seed_info(&self) -> SeedInfo {
+        self.client.seed_info()
+    }
+
+    /// Fetches the retired pair's server seed right away and audits its bets in the background,
+    /// the result lands in the ledger on a later bet.
+    async fn rotate_seed(&mut self) -> Result<SeedInfo, BetError> {
+        let retired = self.client.seed_info();
+        self.client.rotate_seed().await?;
+
+        let site = self.client.site();
+        let bets = self.ledger.retire_pair(retired.clone()).to_vec();
+        if bets.is_empty() {
+            return Ok(retired);
+        }
+        let mut revealed = HashMap::new();
+        for server_seed_hash in audit::commitments(site, &bets) {
+            // Seeds the site won't reveal leave their bets unverified.
+            if let Ok(server_seed) = self.client.fetch_revealed_seed(&server_seed_hash).await {
+                self.ledger
+                    .record_reveal(server_seed_hash.clone(), server_seed.clone());
+                revealed.insert(server_seed_hash, server_seed);
+            }
+        }
+        let count = bets.len();
+        let house_edges = self.ledger.house_edges().clone();
+        let handle =
+            tokio::task::spawn_blocking(move || audit::audit(site, &bets, &revealed, &house_edges));
+        self.audits.push((retired.clone(), count, handle));
+
+        Ok(retired)
+    }
+
+    async fn revealed_seed(&mut self, server_seed_hash: &str) -> Result<String, BetError> {
+        self.client.fetch_revealed_seed(server_seed_hash).await
+    }
+
+    async fn finish_audits(&mut self) -> Result<(), BetError> {
+        self.collect_audits(true).await
+    }
+
+    async fn reconcile(&mut self) -> Result<Reconciliation, BetError> {
+        let symbol = self.client.symbol();
+        let site_balance = self.fetch_site_balance().await?;
+        let checked = self.ledger.unreconciled_ids(&symbol, RECONCILE_LOOKUPS);
+        let site_bets = if checked.is_empty() {
+            None
+        } else {
+            self.client.fetch_bets(&checked).await?
+        };
+
+        Ok(self
+            .ledger
+            .reconcile(&symbol, site_balance, &checked, site_bets.as_deref()))
+    }
+
+    async fn list_balances(&mut self) -> Result<Vec<CurrencyBalance>, BetError> {
+        self.client.fetch_balances().await
+    }
+
+    /// Parks the current currency's book and carries on with the one of `currency`, fresh from
+    /// its site balance unless the session bet in it before.
+    async fn switch_currency(&mut self, currency: &Currency) -> Result<(), BetError> {
+        let from = self.client.symbol();
+        if currency.to_string() == from {
+            return Ok(());
+        }
+
+        self.client.switch_currency(currency).await?;
+        let site_balance = match self.fetch_site_balance().await {
+            Ok(site_balance) => site_balance,
+            Err(err) => {
+                if let Ok(currency) = from.parse::<Currency>() {
+                    self.client.switch_currency(&currency).await?;
+                }
+                return Err(err);
+            }
+        };
+        let to = self.client.symbol();
+
+        let book = self.take_book();
+        self.parked.insert(from.clone(), book);
+        let book = self.parked.remove(&to).unwrap_or_default();
+        self.put_book(book);
+        self.site_balance = site_balance;
+        self.strategy.set_capabilities(&self.client.capabilities());
+        self.start_over();
+        self.ledger.switch(&from, &to, site_balance);
+        self.ledger
+            .record_house_edge(&to, self.client.capabilities().house_edge);
+
+        Ok(())
+    }
+
+    fn set_strategy_params(&mut self, params: toml::Table) -> Result<(), toml::de::Error> {
+        self.strategy.set_params(params)
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(SessionState {
+            rolls: self.rolls,
+            history: self.history.clone(),
+            current_bet: self.current_bet,
+            chance: self.chance,
+            multiplier: self.multiplier,
+            balance: self.balance,
+            initial_balance: self.initial_balance,
+            profit: self.profit,
+            wins: self.wins,
+            losses: self.losses,
+            strategy: self.strategy.save_state(),
+            client: self.client.save_state(),
+            ledger: self.ledger.clone(),
+            symbol: self.client.symbol(),
+            parked: self.parked.clone(),
+        })
+        .unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        let state: SessionState = serde_json::from_value(state)?;
+        self.strategy.load_state(state.strategy)?;
+        self.client.load_state(state.client)?;
+        self.strategy.set_capabilities(&self.client.capabilities());
+
+        self.rolls = state.rolls;
+        self.history = state.history;
+        self.current_bet = state.current_bet;
+        self.chance = state.chance;
+        self.multiplier = state.multiplier;
+        self.balance = state.balance;
+        self.initial_balance = state.initial_balance;
+        self.profit = state.profit;
+        self.wins = state.wins;
+        self.losses = state.losses;
+        // Snapshots from before the ledger recorded its site.
+        self.ledger = state.ledger.or_site(self.client.site());
+        self.parked = state.parked;
+
+        // The config may have moved the session to another currency since.
+        let symbol = self.client.symbol();
+        if !state.symbol.is_empty() && state.symbol != symbol {
+            let site_balance = self.site_balance;
+            let book = self.take_book();
+            self.parked.insert(state.symbol, book);
+            let book = self.parked.remove(&symbol);
+            let fresh = book.is_none();
+            self.put_book(book.unwrap_or_default());
+            self.site_balance = site_balance;
+            if fresh {
+                self.start_over();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_history(&self) -> Vec<BetOutcome> {
+        self.history.clone()
+    }
+
+    fn get_history_size(&self) -> usize {
+        self.history_size
+    }
+
+    fn get_rolls(&self) -> u64 {
+        self.rolls
+    }
+
+    fn get_current_bet(&self) -> f32 {
+        self.current_bet
+    }
+
+    fn get_current_multiplier(&self) -> f32 {
+        self.multiplier
+    }
+
+    fn get_profit(&self) -> f32 {
+        self.profit
+    }
+
+    fn get_balance(&self) -> f32 {
+        self.balance
+    }
+
+    fn get_symbol(&self) -> String {
+        self.client.symbol()
+    }
+
+    fn capabilities(&self) -> SiteCapabilities {
+        self.client.capabilities()
+    }
+
+    fn get_currency_profits(&self) -> Vec<(String, f32)> {
+        let mut parked = self
+            .parked
+            .iter()
+            .map(|(symbol, book)| (symbol.clone(), book.profit))
+            .collect::<Vec<(String, f32)>>();
+        parked.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut profits = vec![(self.client.symbol(), self.profit)];
+        profits.extend(parked);
+
+        profits
+    }
+}
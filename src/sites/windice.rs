@@ -0,0 +1,760 @@
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, AUTHORIZATION, USER_AGENT};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    config::SiteConfig,
+    credentials::Secret,
+    sites::{
+        chance_rolls, random_seed, read_json, request::Requester, BetDecision, BetError, BetKind,
+        BetOutcome, CurrencyBalance, SeedInfo, SeedMap, SiteCapabilities, SiteClient, Sites,
+        MAX_ROLL,
+    },
+};
+
+const BASE_URL: &str = "https://windice.io/api/v1/api";
+
+#[derive(Clone, Debug)]
+pub enum Currency {
+    BTC,
+    ETH,
+    LTC,
+    DOGE,
+    BCH,
+    XRP,
+    TRX,
+    USDT,
+    BNB,
+}
+
+impl Currency {
+    pub fn get_min_bet(&self) -> f32 {
+        match self {
+            Self::BTC => 0.00000001,
+            Self::ETH => 0.0000001,
+            Self::LTC => 0.00001,
+            Self::DOGE => 0.1,
+            Self::BCH => 0.00001,
+            Self::XRP => 0.01,
+            Self::TRX => 0.1,
+            Self::USDT => 0.01,
+            Self::BNB => 0.00001,
+        }
+    }
+
+    /// Chances run from 0.01 to 95 percent.
+    pub fn capabilities(&self) -> SiteCapabilities {
+        let house_edge = Sites::Windice.house_edge();
+
+        SiteCapabilities {
+            min_bet: self.get_min_bet(),
+            max_bet: None,
+            min_payout: (100. - house_edge) / 95.,
+            max_payout: (100. - house_edge) / 0.01,
+            house_edge,
+            max_profit: None,
+            precision: 8,
+            ranges: Sites::Windice.ranges(),
+        }
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Self::BTC => "BTC",
+            Self::ETH => "ETH",
+            Self::LTC => "LTC",
+            Self::DOGE => "DOGE",
+            Self::BCH => "BCH",
+            Self::XRP => "XRP",
+            Self::TRX => "TRX",
+            Self::USDT => "USDT",
+            Self::BNB => "BNB",
+        };
+
+        f.write_str(symbol)
+    }
+}
+
+impl TryFrom<&crate::currency::Currency> for Currency {
+    type Error = ();
+
+    fn try_from(value: &crate::currency::Currency) -> Result<Self, Self::Error> {
+        use crate::currency::Currency as TomlCurrency;
+
+        match value {
+            TomlCurrency::BTC => Ok(Self::BTC),
+            TomlCurrency::ETH => Ok(Self::ETH),
+            TomlCurrency::LTC => Ok(Self::LTC),
+            TomlCurrency::DOGE => Ok(Self::DOGE),
+            TomlCurrency::BCH => Ok(Self::BCH),
+            TomlCurrency::XRP => Ok(Self::XRP),
+            TomlCurrency::TRX => Ok(Self::TRX),
+            TomlCurrency::USDT => Ok(Self::USDT),
+            TomlCurrency::BNB => Ok(Self::BNB),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Every reply is wrapped in `{"status": "success", "data": ...}`, failures carry a `message`.
+#[derive(Debug, Deserialize)]
+pub struct Reply {
+    pub status: String,
+    pub data: Option<serde_json::Value>,
+    pub message: Option<String>,
+}
+
+/// `game` is `in` to win inside `low..=high` and `out` to win outside of it, rolls are
+/// `0..=9999`.
+#[derive(Debug, Serialize)]
+pub struct Roll {
+    pub curr: String,
+    pub bet: f64,
+    pub game: String,
+    pub low: u32,
+    pub high: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollResult {
+    /// Hash of the server seed the roll came from.
+    pub hash: String,
+    pub nonce: u64,
+    pub result: u32,
+    pub bet: f64,
+    /// Total paid out, 0 on a loss.
+    pub win: f64,
+    pub payout: f64,
+    pub curr: String,
+    pub game: String,
+    pub low: u32,
+    pub high: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct User {
+    pub username: String,
+    /// Balance per lowercase currency symbol, the API sends them as strings or numbers.
+    pub balance: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Seed {
+    pub client: String,
+    pub hash: String,
+    pub nonce: u64,
+}
+
+/// The retired pair, sent back when the client seed changes.
+#[derive(Debug, Deserialize)]
+pub struct RevealedSeed {
+    pub server: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeedChange {
+    pub client: String,
+    pub revealed: Option<RevealedSeed>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct WindiceState {
+    client_seed: String,
+    server_seed_hash: String,
+    #[serde(default)]
+    previous_hash: String,
+    nonce: u64,
+    #[serde(default)]
+    revealed: SeedMap,
+}
+
+pub struct Windice {
+    client: reqwest::Client,
+    requester: Requester,
+    base_url: String,
+    api_key: Secret,
+    currency: Currency,
+    client_seed: String,
+    server_seed_hash: String,
+    /// The retired pair's server seed hash, rolls only carry the active one.
+    previous_hash: String,
+    nonce: u64,
+    revealed: SeedMap,
+}
+
+impl Default for Windice {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            requester: Requester::new(Sites::Windice),
+            base_url: BASE_URL.to_string(),
+            api_key: Secret::default(),
+            currency: Currency::BTC,
+            client_seed: String::new(),
+            server_seed_hash: String::new(),
+            previous_hash: String::new(),
+            nonce: 0,
+            revealed: SeedMap::default(),
+        }
+    }
+}
+
+impl Windice {
+    /// Points the client at another API root, e.g. a local mock server.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+
+        self
+    }
+
+    pub fn with_requester(mut self, requester: Requester) -> Self {
+        self.requester = requester;
+
+        self
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T, BetError> {
+        Self::read_reply(self.requester.send(request).await?).await
+    }
+
+    /// Unwraps the `data` of a successful reply, a failed one carries the reason.
+    async fn read_reply<T: DeserializeOwned>(res: reqwest::Response) -> Result<T, BetError> {
+        let reply: Reply = read_json(res).await?;
+
+        match reply {
+            Reply {
+                status,
+                data: Some(data),
+                ..
+            } if status == "success" => {
+                serde_json::from_value(data.clone()).map_err(|_| BetError::Parse {
+                    raw: data.to_string(),
+                })
+            }
+            Reply {
+                status, message, ..
+            } => Err(BetError::BetRejected {
+                reason: message.unwrap_or(status),
+            }),
+        }
+    }
+
+    async fn fetch_seed(&mut self) -> Result<(), BetError> {
+        let seed: Seed = self
+            .send(self.client.get(format!("{}/seed", self.base_url)))
+            .await?;
+
+        self.client_seed = seed.client;
+        if seed.hash != self.server_seed_hash {
+            self.previous_hash = std::mem::replace(&mut self.server_seed_hash, seed.hash);
+        }
+        self.nonce = seed.nonce;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SiteClient for Windice {
+    fn site(&self) -> Sites {
+        Sites::Windice
+    }
+
+    fn symbol(&self) -> String {
+        self.currency.to_string()
+    }
+
+    fn capabilities(&self) -> SiteCapabilities {
+        self.currency.capabilities()
+    }
+
+    fn odds(&self, chance: f32, _multiplier: f32) -> (f32, f32) {
+        let capabilities = self.capabilities();
+        let chance = capabilities.clamp_chance(chance);
+
+        (chance, capabilities.multiplier(chance))
+    }
+
+    async fn switch_currency(
+        &mut self,
+        currency: &crate::currency::Currency,
+    ) -> Result<(), BetError> {
+        self.currency =
+            Currency::try_from(currency).map_err(|_| BetError::UnsupportedCurrency {
+                site: Sites::Windice,
+                currency: currency.clone(),
+            })?;
+
+        Ok(())
+    }
+
+    /// The API key is sent as a header on every request, fetching the seed checks it.
+    async fn login(&mut self) -> Result<(), BetError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            self.api_key
+                .expose()
+                .parse()
+                .map_err(|_| BetError::LoginFailed {
+                    reason: "the API key isn't a valid header value".to_string(),
+                })?,
+        );
+        headers.insert(USER_AGENT, "Mozilla/5.0".parse().unwrap());
+        self.client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()?;
+
+        self.fetch_seed().await
+    }
+
+    async fn place_bet(&mut self, wager: &BetDecision) -> Result<BetOutcome, BetError> {
+        let width = chance_rolls(wager.chance).clamp(1, MAX_ROLL);
+        let (game, low, high) = match wager.kind {
+            BetKind::Over => ("in", 10000 - width, 9999),
+            BetKind::Under => ("in", 0, width - 1),
+            BetKind::InRange(low, high) => ("in", low, high),
+            BetKind::OutOfRange(low, high) => ("out", low, high),
+        };
+
+        let res = self
+            .requester
+            .send_bet(
+                self.client
+                    .post(format!("{}/roll", self.base_url))
+                    .json(&Roll {
+                        curr: self.currency.to_string().to_lowercase(),
+                        bet: format!("{:.8}", wager.amount).parse().unwrap_or(0.),
+                        game: game.to_string(),
+                        low,
+                        high,
+                    }),
+            )
+            .await?;
+        let roll: RollResult = Self::read_reply(res).await?;
+        self.nonce = roll.nonce + 1;
+
+        let mut bet_result: BetOutcome = roll.into();
+        bet_result.hash_previous_roll = self.previous_hash.clone();
+        bet_result.client_seed = self.client_seed.clone();
+
+        Ok(bet_result.complete(wager, Sites::Windice))
+    }
+
+    async fn fetch_balances(&mut self) -> Result<Vec<CurrencyBalance>, BetError> {
+        let user: User = self
+            .send(self.client.get(format!("{}/user", self.base_url)))
+            .await?;
+
+        let balances = user
+            .balance
+            .into_iter()
+            .map(|(symbol, amount)| CurrencyBalance {
+                symbol: symbol.to_uppercase(),
+                amount: match amount {
+                    serde_json::Value::String(amount) => amount.parse::<f32>().unwrap_or(0.),
+                    amount => amount.as_f64().unwrap_or(0.) as f32,
+                },
+            })
+            .collect();
+
+        Ok(balances)
+    }
+
+    /// Sets a new client seed, Windice reveals the old server seed and commits to a new one.
+    async fn rotate_seed(&mut self) -> Result<(), BetError> {
+        let change: SeedChange = self
+            .send(
+                self.client
+                    .post(format!("{}/seed", self.base_url))
+                    .json(&json!({ "value": random_seed(30) })),
+            )
+            .await?;
+        if let Some(revealed) = change.revealed {
+            self.revealed.insert(revealed.hash, revealed.server);
+        }
+
+        self.fetch_seed().await
+    }
+
+    fn seed_info(&self) -> SeedInfo {
+        SeedInfo {
+            server_seed_hash: self.server_seed_hash.clone(),
+            client_seed: self.client_seed.clone(),
+            nonce: self.nonce,
+        }
+    }
+
+    async fn fetch_revealed_seed(&mut self, server_seed_hash: &str) -> Result<String, BetError> {
+        self.revealed
+            .get(server_seed_hash)
+            .map(str::to_string)
+            .ok_or_else(|| BetError::SeedUnavailable {
+                server_seed_hash: server_seed_hash.to_string(),
+            })
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(WindiceState {
+            client_seed: self.client_seed.clone(),
+            server_seed_hash: self.server_seed_hash.clone(),
+            previous_hash: self.previous_hash.clone(),
+            nonce: self.nonce,
+            revealed: self.revealed.clone(),
+        })
+        .unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        let state: WindiceState = serde_json::from_value(state)?;
+
+        self.client_seed = state.client_seed;
+        self.server_seed_hash = state.server_seed_hash;
+        self.previous_hash = state.previous_hash;
+        self.nonce = state.nonce;
+        self.revealed = state.revealed;
+
+        Ok(())
+    }
+}
+
+impl SiteConfig for Windice {
+    fn with_api_key(mut self, api_key: Secret) -> Self
+    where
+        Self: Sized,
+    {
+        self.api_key = api_key;
+
+        self
+    }
+
+    fn with_currency(mut self, currency: crate::currency::Currency) -> Self
+    where
+        Self: Sized,
+    {
+        if let Ok(currency) = Currency::try_from(&currency) {
+            self.currency = currency;
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::sites::mock::{MockServer, Request, Response};
+    use crate::sites::session::BettingSession;
+    use crate::sites::Site;
+
+    const API_KEY: &str = "test-key";
+
+    struct Account {
+        balance: f64,
+        client_seed: String,
+        server_seed: String,
+        hash: String,
+        nonce: u64,
+        next_roll: u32,
+    }
+
+    fn success(data: serde_json::Value) -> Response {
+        Response::json(200, json!({ "status": "success", "data": data }))
+    }
+
+    /// Settles bets against `next_roll` and keeps the account in `account`.
+    async fn mock_windice(account: Arc<Mutex<Account>>) -> MockServer {
+        MockServer::start(move |request: &Request| {
+            if request.header("authorization") != Some(API_KEY) {
+                return Response::json(
+                    401,
+                    json!({ "status": "error", "message": "Unauthorized" }),
+                );
+            }
+
+            let mut account = account.lock().unwrap();
+            match (request.method.as_str(), request.path.as_str()) {
+                ("GET", "/user") => success(json!({
+                    "username": "tester",
+                    "balance": { "btc": format!("{:.8}", account.balance), "eth": 0.5 },
+                })),
+                ("GET", "/seed") => success(json!({
+                    "client": account.client_seed,
+                    "hash": account.hash,
+                    "nonce": account.nonce,
+                })),
+                ("POST", "/seed") => {
+                    account.client_seed = request.json()["value"].as_str().unwrap().to_string();
+                    let revealed = json!({ "server": account.server_seed, "hash": account.hash });
+                    account.server_seed = "server-b".to_string();
+                    account.hash = "b".repeat(64);
+                    account.nonce = 0;
+
+                    success(json!({ "client": account.client_seed, "revealed": revealed }))
+                }
+                ("POST", "/roll") => {
+                    let roll = request.json();
+                    let bet = roll["bet"].as_f64().unwrap();
+                    if bet > account.balance {
+                        return Response::json(
+                            200,
+                            json!({ "status": "error", "message": "Insufficient funds" }),
+                        );
+                    }
+
+                    let (low, high) = (
+                        roll["low"].as_u64().unwrap(),
+                        roll["high"].as_u64().unwrap(),
+                    );
+                    let inside = roll["game"] == "in";
+                    let width = if inside {
+                        high - low + 1
+                    } else {
+                        10000 - (high - low + 1)
+                    };
+                    let payout = 99. / (width as f64 / 100.);
+                    let result = account.next_roll;
+                    let win = if (low..=high).contains(&(result as u64)) == inside {
+                        bet * payout
+                    } else {
+                        0.
+                    };
+                    account.balance += win - bet;
+                    let nonce = account.nonce;
+                    account.nonce += 1;
+
+                    success(json!({
+                        "hash": account.hash,
+                        "nonce": nonce,
+                        "result": result,
+                        "bet": bet,
+                        "win": win,
+                        "payout": payout,
+                        "curr": roll["curr"],
+                        "game": roll["game"],
+                        "low": low,
+                        "high": high,
+                    }))
+                }
+                _ => Response::raw(404, "not found"),
+            }
+        })
+        .await
+    }
+
+    fn account() -> Arc<Mutex<Account>> {
+        Arc::new(Mutex::new(Account {
+            balance: 0.001,
+            client_seed: "client".to_string(),
+            server_seed: "server-a".to_string(),
+            hash: "a".repeat(64),
+            nonce: 7,
+            next_roll: 9000,
+        }))
+    }
+
+    fn windice(server: &MockServer) -> Windice {
+        Windice::default()
+            .with_base_url(server.base_url.clone())
+            .with_requester(Requester::new(Sites::Windice).with_rate(1000.))
+            .with_api_key(Secret::new(API_KEY.to_string()))
+            .with_currency(crate::currency::Currency::BTC)
+    }
+
+    #[tokio::test]
+    async fn login_reads_seed_and_balances() {
+        let server = mock_windice(account()).await;
+        let mut windice = windice(&server);
+
+        windice.login().await.unwrap();
+        let seed = windice.seed_info();
+        assert_eq!(seed.client_seed, "client");
+        assert_eq!(seed.nonce, 7);
+
+        let balances = windice.fetch_balances().await.unwrap();
+        let btc = balances.iter().find(|balance| balance.symbol == "BTC");
+        assert_eq!(btc.map(|balance| balance.amount), Some(0.001));
+        assert!(balances.iter().any(|balance| balance.symbol == "ETH"));
+    }
+
+    #[tokio::test]
+    async fn over_bet_maps_into_bet_result() {
+        let account = account();
+        let server = mock_windice(Arc::clone(&account)).await;
+        let mut windice = windice(&server);
+        windice.login().await.unwrap();
+
+        let bet_result = windice
+            .place_bet(&BetDecision {
+                amount: 0.0001,
+                chance: 49.5,
+                multiplier: 2.,
+                kind: BetKind::Over,
+            })
+            .await
+            .unwrap();
+
+        let roll = server.requests().last().unwrap().json();
+        assert_eq!(roll["curr"], "btc");
+        assert_eq!(
+            (roll["low"].as_u64(), roll["high"].as_u64()),
+            (Some(5050), Some(9999))
+        );
+        assert!(bet_result.result);
+        assert!(bet_result.is_high);
+        assert_eq!(bet_result.number, 9000);
+        assert_eq!(bet_result.threshold, 5050);
+        assert_eq!(
+            bet_result.threshold,
+            BetKind::Over.threshold(49.5, MAX_ROLL)
+        );
+        assert_eq!((bet_result.chance, bet_result.bet_amount), (49.5, 0.0001));
+        assert!(bet_result.timestamp > 0);
+        assert_eq!(bet_result.nonce, 7);
+        assert_eq!(bet_result.client_seed, "client");
+        assert!((bet_result.win_amount - 0.0001).abs() < 1e-7);
+        assert_eq!(windice.seed_info().nonce, 8);
+
+        account.lock().unwrap().next_roll = 9000;
+        let bet_result = windice
+            .place_bet(&BetDecision {
+                amount: 0.0001,
+                chance: 0.29,
+                multiplier: 341.,
+                kind: BetKind::Under,
+            })
+            .await
+            .unwrap();
+        let roll = server.requests().last().unwrap().json();
+        assert_eq!(roll["high"].as_u64(), Some(28));
+        assert_eq!(bet_result.chance, 0.29);
+        assert!(!bet_result.result);
+        assert!((bet_result.win_amount + 0.0001).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn ranges_map_to_in_and_out_games() {
+        let server = mock_windice(account()).await;
+        let mut windice = windice(&server);
+        windice.login().await.unwrap();
+
+        let bet_result = windice
+            .place_bet(&BetDecision {
+                amount: 0.0001,
+                chance: 10.,
+                multiplier: 9.9,
+                kind: BetKind::InRange(8500, 9499),
+            })
+            .await
+            .unwrap();
+        let roll = server.requests().last().unwrap().json();
+        assert_eq!(roll["game"], "in");
+        assert_eq!(
+            (roll["low"].as_u64(), roll["high"].as_u64()),
+            (Some(8500), Some(9499))
+        );
+        assert!(bet_result.result);
+        assert_eq!(bet_result.kind, BetKind::InRange(8500, 9499));
+
+        let bet_result = windice
+            .place_bet(&BetDecision {
+                amount: 0.0001,
+                chance: 90.,
+                multiplier: 1.1,
+                kind: BetKind::OutOfRange(8500, 9499),
+            })
+            .await
+            .unwrap();
+        assert_eq!(server.requests().last().unwrap().json()["game"], "out");
+        assert!(!bet_result.result);
+        assert_eq!(bet_result.kind, BetKind::OutOfRange(8500, 9499));
+    }
+
+    #[tokio::test]
+    async fn rotate_seed_reveals_the_retired_pair() {
+        let server = mock_windice(account()).await;
+        let mut windice = windice(&server);
+        windice.login().await.unwrap();
+
+        assert!(matches!(
+            windice.fetch_revealed_seed(&"a".repeat(64)).await,
+            Err(BetError::SeedUnavailable { .. })
+        ));
+
+        windice.rotate_seed().await.unwrap();
+        let seed = windice.seed_info();
+        assert_ne!(seed.client_seed, "client");
+        assert_eq!(seed.server_seed_hash, "b".repeat(64));
+        assert_eq!(seed.nonce, 0);
+        assert_eq!(
+            windice.fetch_revealed_seed(&"a".repeat(64)).await.unwrap(),
+            "server-a"
+        );
+
+        let bet_result = windice
+            .place_bet(&BetDecision {
+                amount: 0.0001,
+                chance: 49.5,
+                multiplier: 2.,
+                kind: BetKind::Over,
+            })
+            .await
+            .unwrap();
+        assert_eq!(bet_result.hash_previous_roll, "a".repeat(64));
+        assert_eq!(bet_result.hash_next_roll, "b".repeat(64));
+    }
+
+    #[tokio::test]
+    async fn errors_are_typed() {
+        let server = mock_windice(account()).await;
+
+        let mut windice = windice(&server).with_api_key(Secret::new("wrong".to_string()));
+        assert!(matches!(windice.login().await, Err(BetError::AuthExpired)));
+
+        let mut windice = self::windice(&server);
+        windice.login().await.unwrap();
+        let err = windice
+            .place_bet(&BetDecision {
+                amount: 1.,
+                chance: 50.,
+                multiplier: 1.98,
+                kind: BetKind::Over,
+            })
+            .await;
+        assert!(
+            matches!(err, Err(BetError::BetRejected { reason }) if reason == "Insufficient funds")
+        );
+    }
+
+    #[tokio::test]
+    async fn session_tracks_balance_over_the_mock() {
+        let server = mock_windice(account()).await;
+        let mut session = BettingSession::new(
+            Box::new(windice(&server)),
+            crate::strategies::from_toml(crate::config::TomlStrategies::None, 1e-8),
+        );
+        session.login().await.unwrap();
+        assert_eq!(session.get_balance(), 0.001);
+
+        let bet_result = session.do_bet(5000., 50.).await.unwrap();
+        if bet_result.result {
+            session.on_win(&bet_result);
+        } else {
+            session.on_lose(&bet_result);
+        }
+        assert_eq!(session.get_rolls(), 1);
+        assert!((session.get_profit() - bet_result.win_amount).abs() < 1e-12);
+    }
+}
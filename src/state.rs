@@ -0,0 +1,97 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::SessionStats;
+use crate::sites::ledger::Ledger;
+
+/// Everything needed to resume a session after a shutdown.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SessionSnapshot {
+    pub name: String,
+    pub stats: SessionStats,
+    pub prediction: f32,
+    pub confidence: f32,
+    /// Site bookkeeping, history and the strategy state, see `Site::save_state`.
+    pub site: serde_json::Value,
+}
+
+fn snapshot_path(state_dir: &Path, name: &str) -> PathBuf {
+    let file_name = name
+        .chars()
+        .map(|chr| {
+            if chr.is_ascii_alphanumeric() || chr == '-' {
+                chr
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    state_dir.join(format!("{file_name}.json"))
+}
+
+/// Writes the snapshot next to its final path first so a crash mid-write keeps the old one.
+pub fn save(state_dir: &Path, snapshot: &SessionSnapshot) -> io::Result<()> {
+    std::fs::create_dir_all(state_dir)?;
+
+    let path = snapshot_path(state_dir, &snapshot.name);
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(snapshot)?)?;
+    std::fs::rename(tmp_path, path)
+}
+
+pub fn load(state_dir: &Path, name: &str) -> io::Result<Option<SessionSnapshot>> {
+    let path = snapshot_path(state_dir, name);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents = std::fs::read(path)?;
+
+    Ok(Some(serde_json::from_slice(&contents)?))
+}
+
+/// The ledger of a snapshot file, or of a file holding a bare `Ledger`.
+pub fn load_ledger(path: &Path) -> io::Result<Ledger> {
+    let value: serde_json::Value = serde_json::from_slice(&std::fs::read(path)?)?;
+    // Ledgers have a `site` field of their own, only snapshots nest one under it.
+    let ledger = match value.get("site").and_then(|site| site.get("ledger")) {
+        Some(ledger) => ledger.clone(),
+        None => value,
+    };
+
+    Ok(serde_json::from_value(ledger)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sites::Sites;
+
+    #[test]
+    fn ledgers_load_bare_or_from_a_snapshot() {
+        let state_dir = std::env::temp_dir().join(format!("state-{}", std::process::id()));
+        std::fs::create_dir_all(&state_dir).unwrap();
+        let ledger = serde_json::to_value(Ledger::new(Sites::Windice)).unwrap();
+
+        let bare_path = state_dir.join("ledger.json");
+        std::fs::write(&bare_path, serde_json::to_vec(&ledger).unwrap()).unwrap();
+        let snapshot = SessionSnapshot {
+            name: "session".to_string(),
+            stats: SessionStats::default(),
+            prediction: 0.,
+            confidence: 0.,
+            site: serde_json::json!({ "ledger": ledger }),
+        };
+        save(&state_dir, &snapshot).unwrap();
+
+        let bare = load_ledger(&bare_path);
+        let nested = load_ledger(&snapshot_path(&state_dir, "session"));
+        std::fs::remove_dir_all(&state_dir).unwrap();
+
+        assert_eq!(bare.unwrap().site(), Some(Sites::Windice));
+        assert_eq!(nested.unwrap().site(), Some(Sites::Windice));
+    }
+}
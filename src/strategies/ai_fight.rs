@@ -1,8 +1,30 @@
-use crate::sites::BetResult;
-use crate::strategies::Strategy;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+use crate::sites::{BetDecision, BetKind, BetOutcome, SiteCapabilities};
+use crate::strategies::{parse_params, Strategy};
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AIFightParams {
+    pub house_percent: f32,
+    pub max_chance: f32,
+    pub min_chance: f32,
+}
+
+impl Default for AIFightParams {
+    fn default() -> Self {
+        Self {
+            house_percent: 5.,
+            max_chance: 50.,
+            min_chance: 0.02,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct AIFight {
+    #[serde(default)]
+    params: AIFightParams,
     high: bool,
     win_streak: usize,
     loss_streak: usize,
@@ -11,17 +33,17 @@ pub struct AIFight {
     multiplier: f32,
     bank: f32,
     current_bet: f32,
-    max_chance: f32,
-    min_chance: f32,
     chance: f32,
-    house_percent: f32,
     profit: f32,
     initialized: bool,
+    #[serde(skip)]
+    capabilities: SiteCapabilities,
 }
 
 impl Default for AIFight {
     fn default() -> Self {
         Self {
+            params: AIFightParams::default(),
             high: false,
             win_streak: 0,
             loss_streak: 0,
@@ -30,12 +52,10 @@ impl Default for AIFight {
             multiplier: 2.,
             bank: 1e-8,
             current_bet: 1e-8,
-            max_chance: 50.,
-            min_chance: 0.02,
             chance: 50.,
-            house_percent: 5.,
             profit: 0.,
             initialized: false,
+            capabilities: SiteCapabilities::default(),
         }
     }
 }
@@ -63,11 +83,27 @@ impl Strategy for AIFight {
         self.bank = balance;
     }
 
-    fn get_next_bet(&mut self, prediction: f32, _confidence: f32) -> (f32, f32, f32, bool) {
+    fn set_capabilities(&mut self, capabilities: &SiteCapabilities) {
+        self.min_bet = capabilities.min_bet;
+        self.capabilities = capabilities.clone();
+    }
+
+    fn set_params(&mut self, params: toml::Table) -> Result<(), toml::de::Error> {
+        self.params = parse_params(params)?;
+
+        Ok(())
+    }
+
+    fn get_next_bet(&mut self, prediction: f32, _confidence: f32) -> BetDecision {
         self.high = prediction > 5000.;
         if !self.initialized && prediction == 0. {
             self.initialized = true;
-            return (1e-8, 2., 50., self.high);
+            return BetDecision {
+                amount: 1e-8,
+                chance: 50.,
+                multiplier: 2.,
+                kind: BetKind::from_high(self.high),
+            };
         }
 
         self.rolls += 1;
@@ -78,26 +114,35 @@ impl Strategy for AIFight {
                 self.loss_streak as f32 - self.win_streak as f32
             }) * 0.05;
 
-        self.chance = (50. + self.house_percent) * (1. - ((prediction - 5000.).abs() / 5000.));
-        self.chance = self.chance.max(self.min_chance).min(self.max_chance);
+        self.chance =
+            (50. + self.params.house_percent) * (1. - ((prediction - 5000.).abs() / 5000.));
+        self.chance = self
+            .chance
+            .max(self.params.min_chance)
+            .min(self.params.max_chance);
 
         self.multiplier = 1. / (self.chance / 100.);
-        self.multiplier = self.multiplier.clamp(1.01, 4750.);
+        self.multiplier = self.capabilities.clamp_multiplier(self.multiplier);
         let bet_win_adjustment = self.min_bet * (self.win_streak as f32 * 0.5);
         self.current_bet = bet_win_adjustment + (self.bank * 1e-4 * bet_multiplier);
         self.current_bet = self.current_bet.max(self.min_bet).min(self.bank);
 
-        (self.current_bet, self.multiplier, self.chance, self.high)
+        BetDecision {
+            amount: self.current_bet,
+            chance: self.chance,
+            multiplier: self.multiplier,
+            kind: BetKind::from_high(self.high),
+        }
     }
 
-    fn on_win(&mut self, bet_result: &BetResult) {
+    fn on_win(&mut self, bet_result: &BetOutcome) {
         self.bank += bet_result.win_amount;
         self.profit += bet_result.win_amount;
         self.win_streak += 1;
         self.loss_streak = self.loss_streak.saturating_sub(1);
     }
 
-    fn on_lose(&mut self, bet_result: &BetResult) {
+    fn on_lose(&mut self, bet_result: &BetOutcome) {
         self.bank -= bet_result.win_amount;
         self.profit -= bet_result.win_amount;
         self.loss_streak += 1;
@@ -111,4 +156,16 @@ impl Strategy for AIFight {
     fn get_profit(&self) -> f32 {
         self.profit
     }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        let params = std::mem::take(&mut self.params);
+        *self = serde_json::from_value(state)?;
+        self.params = params;
+
+        Ok(())
+    }
 }
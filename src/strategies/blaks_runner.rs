@@ -1,9 +1,43 @@
-use crate::sites::BetResult;
-use crate::strategies::Strategy;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+use crate::sites::{BetDecision, BetKind, BetOutcome, SiteCapabilities};
+use crate::strategies::{parse_params, Strategy};
+
+/// Values `reset` restores, exposed through `[<site>.strategy_params]`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlaksRunnerParams {
+    pub base_chance: f32,
+    pub chance_inc: f32,
+    pub inc_divisor: f32,
+    pub site_max_profit: f32,
+    pub toggle_high_low: bool,
+    pub max_win_mult: u64,
+    pub house_percent: f32,
+    pub max_bet: f32,
+    pub chance_max: f32,
+}
+
+impl Default for BlaksRunnerParams {
+    fn default() -> Self {
+        Self {
+            base_chance: 4.4,
+            chance_inc: 0.00010,
+            inc_divisor: 10000000.,
+            site_max_profit: 0.,
+            toggle_high_low: false,
+            max_win_mult: 512,
+            house_percent: 5.,
+            max_bet: 0.,
+            chance_max: 1.5,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct BlaksRunner5_0 {
     initialized: bool,
+    params: BlaksRunnerParams,
     pub base_chance: f32,
     pub chance_inc: f32,
     pub inc_divisor: f32,
@@ -42,6 +76,8 @@ pub struct BlaksRunner5_0 {
     pub min_chance: f32,
     pub max_chance: f32,
     pub win_target: f32,
+    #[serde(skip)]
+    capabilities: SiteCapabilities,
 }
 
 impl BlaksRunner5_0 {
@@ -110,20 +146,23 @@ impl BlaksRunner5_0 {
         }
     }
 
+    fn apply_params(&mut self) {
+        self.base_chance = self.params.base_chance;
+        self.chance_inc = self.params.chance_inc;
+        self.inc_divisor = self.params.inc_divisor;
+        self.site_max_profit = self.params.site_max_profit;
+        self.toggle_high_low = self.params.toggle_high_low;
+        self.max_win_mult = self.params.max_win_mult;
+        self.house_percent = self.params.house_percent;
+        self.max_bet = self.params.max_bet;
+        self.chance_max = self.params.chance_max;
+    }
+
     fn reset(&mut self) {
-        let inc_divisor = 10000000.;
-        self.base_chance = 4.4;
-        self.chance_inc = 0.00010;
-        self.inc_divisor = inc_divisor;
-        self.site_max_profit = 0.;
-        self.toggle_high_low = false;
+        self.apply_params();
         self.bet_high = false;
         self.rest_time = 0.;
-        self.max_win_mult = 512;
-        self.house_percent = 5.;
-        self.max_bet = 0.;
         self.chance_mult = 1.6666;
-        self.chance_max = 1.5;
         self.total_profit = 0.;
         self.win_mult = 1.;
         self.inc_roll = 0;
@@ -152,6 +191,7 @@ impl Default for BlaksRunner5_0 {
 
         Self {
             initialized: false,
+            params: BlaksRunnerParams::default(),
             base_chance: 1.,
             chance_inc: 0.00010,
             inc_divisor,
@@ -190,6 +230,7 @@ impl Default for BlaksRunner5_0 {
             min_chance: 0.02,
             max_chance: 5.,
             win_target: 0.0001,
+            capabilities: SiteCapabilities::default(),
         }
     }
 }
@@ -222,7 +263,21 @@ impl Strategy for BlaksRunner5_0 {
         self.profit = 0.;
     }
 
-    fn get_next_bet(&mut self, prediction: f32, _confidence: f32) -> (f32, f32, f32, bool) {
+    fn set_capabilities(&mut self, capabilities: &SiteCapabilities) {
+        self.min_bet = capabilities.min_bet;
+        self.capabilities = capabilities.clone();
+    }
+
+    fn set_params(&mut self, params: toml::Table) -> Result<(), toml::de::Error> {
+        self.params = parse_params(params)?;
+        if self.initialized {
+            self.apply_params();
+        }
+
+        Ok(())
+    }
+
+    fn get_next_bet(&mut self, prediction: f32, _confidence: f32) -> BetDecision {
         self.bet_high = prediction > 5000.;
         if prediction > 0. && !self.initialized {
             self.reset();
@@ -236,14 +291,19 @@ impl Strategy for BlaksRunner5_0 {
         println!("{}", self.next_bet);
 
         let mut multiplier = 1. / (self.chance / 100.);
-        multiplier = multiplier.clamp(1.01, 4750.);
+        multiplier = self.capabilities.clamp_multiplier(multiplier);
 
         self.next_bet = self.next_bet.max(self.min_bet);
 
-        (self.next_bet, multiplier, self.chance, self.bet_high)
+        BetDecision {
+            amount: self.next_bet,
+            chance: self.chance,
+            multiplier,
+            kind: BetKind::from_high(self.bet_high),
+        }
     }
 
-    fn on_win(&mut self, bet_result: &BetResult) {
+    fn on_win(&mut self, bet_result: &BetOutcome) {
         self.total_profit += bet_result.win_amount;
         self.chance = self.base_chance;
         self.loss_count = 0;
@@ -270,7 +330,7 @@ impl Strategy for BlaksRunner5_0 {
         self.auto_tune();
     }
 
-    fn on_lose(&mut self, bet_result: &BetResult) {
+    fn on_lose(&mut self, bet_result: &BetOutcome) {
         self.loss_count += 1;
         self.high_low_loss_count += 1;
         self.spent += bet_result.win_amount;
@@ -306,4 +366,19 @@ impl Strategy for BlaksRunner5_0 {
     fn get_win_target(&self) -> f32 {
         self.win_target
     }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        let params = self.params.clone();
+        *self = serde_json::from_value(state)?;
+        self.params = params;
+        if self.initialized {
+            self.apply_params();
+        }
+
+        Ok(())
+    }
 }
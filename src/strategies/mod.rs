@@ -3,7 +3,10 @@ pub mod blaks_runner;
 pub mod my_strategy;
 pub mod none;
 
-use crate::sites::BetResult;
+use serde::de::DeserializeOwned;
+
+use crate::config::TomlStrategies;
+use crate::sites::{BetDecision, BetOutcome, SiteCapabilities};
 
 pub trait Strategy: std::fmt::Debug + Send {
     fn with_initial_bet(self, _initial_bet: f32) -> Self
@@ -27,14 +30,116 @@ pub trait Strategy: std::fmt::Debug + Send {
 
     fn set_balance(&mut self, balance: f32);
 
-    /// Returns: (current_bet, multiplier, chance, high/low)
-    fn get_next_bet(&mut self, prediction: f32, confidence: f32) -> (f32, f32, f32, bool);
-    fn on_win(&mut self, bet_result: &BetResult);
-    fn on_lose(&mut self, bet_result: &BetResult);
+    /// The site's limits, multipliers and stakes are kept inside them.
+    fn set_capabilities(&mut self, capabilities: &SiteCapabilities);
+
+    /// Applies a `strategy_params` table, missing keys fall back to defaults.
+    fn set_params(&mut self, params: toml::Table) -> Result<(), toml::de::Error>;
+
+    /// The bet the strategy wants next, the session fits it into the site's limits.
+    fn get_next_bet(&mut self, prediction: f32, confidence: f32) -> BetDecision;
+    fn on_win(&mut self, bet_result: &BetOutcome);
+    fn on_lose(&mut self, bet_result: &BetOutcome);
     fn get_balance(&self) -> f32;
     fn get_profit(&self) -> f32;
     fn get_win_target(&self) -> f32 {
         0.
     }
     fn reset(&mut self) {}
+
+    /// Serializes the internal counters so a session can be resumed.
+    fn save_state(&self) -> serde_json::Value;
+    /// Resumes the counters of a `save_state` value, the params stay as configured.
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error>;
+}
+
+/// Deserializes a `strategy_params` table into the strategy's params struct.
+pub fn parse_params<T: DeserializeOwned>(params: toml::Table) -> Result<T, toml::de::Error> {
+    toml::Value::Table(params).try_into()
+}
+
+/// Parses a `strategy_params` table for `strategy` and returns it with the defaults filled in.
+pub fn resolve_params(
+    strategy: TomlStrategies,
+    params: toml::Table,
+) -> Result<toml::Table, toml::de::Error> {
+    let resolved = match strategy {
+        TomlStrategies::AiFight => {
+            toml::Table::try_from(parse_params::<ai_fight::AIFightParams>(params)?)
+        }
+        TomlStrategies::BlaksRunner => {
+            toml::Table::try_from(parse_params::<blaks_runner::BlaksRunnerParams>(params)?)
+        }
+        TomlStrategies::MyStrategy => {
+            toml::Table::try_from(parse_params::<my_strategy::MyStratParams>(params)?)
+        }
+        TomlStrategies::None => toml::Table::try_from(parse_params::<none::NoStratParams>(params)?),
+    };
+
+    let mut resolved = resolved.unwrap_or_default();
+    // Params are f32, print them as written rather than with their f64 widening noise.
+    for (_, value) in resolved.iter_mut() {
+        if let toml::Value::Float(float) = value {
+            *float = (*float as f32).to_string().parse().unwrap_or(*float);
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Builds the strategy selected in the config with the site's minimum bet.
+pub fn from_toml(strategy: TomlStrategies, min_bet: f32) -> Box<dyn Strategy> {
+    match strategy {
+        TomlStrategies::AiFight => Box::new(
+            ai_fight::AIFight::default()
+                .with_min_bet(min_bet)
+                .with_initial_bet(min_bet),
+        ),
+        TomlStrategies::BlaksRunner => Box::new(
+            blaks_runner::BlaksRunner5_0::default()
+                .with_min_bet(min_bet)
+                .with_initial_bet(min_bet),
+        ),
+        TomlStrategies::MyStrategy => Box::new(
+            my_strategy::MyStrat::default()
+                .with_min_bet(min_bet)
+                .with_initial_bet(min_bet),
+        ),
+        TomlStrategies::None => Box::new(
+            none::NoStrat::default()
+                .with_min_bet(min_bet)
+                .with_initial_bet(min_bet),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resumed_strategies_keep_the_configured_params() {
+        for strategy in [
+            TomlStrategies::AiFight,
+            TomlStrategies::BlaksRunner,
+            TomlStrategies::MyStrategy,
+            TomlStrategies::None,
+        ] {
+            let mut saved = from_toml(strategy, 1e-8);
+            saved.set_balance(0.5);
+            saved.get_next_bet(7500., 1.);
+            let state = saved.save_state();
+
+            let mut params = toml::Table::new();
+            params.insert("house_percent".to_string(), toml::Value::Float(3.5));
+            let mut resumed = from_toml(strategy, 1e-8);
+            resumed.set_params(params).unwrap();
+            resumed.load_state(state).unwrap();
+
+            let state = resumed.save_state();
+            let params = state.get("params").unwrap_or(&state);
+            assert_eq!(params["house_percent"], 3.5, "{strategy:?}");
+            assert_eq!(resumed.get_balance(), 0.5, "{strategy:?}");
+        }
+    }
 }
@@ -1,16 +1,35 @@
-use crate::sites::BetResult;
-use crate::strategies::Strategy;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+use crate::sites::{BetDecision, BetKind, BetOutcome, SiteCapabilities};
+use crate::strategies::{parse_params, Strategy};
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MyStratParams {
+    pub house_percent: f32,
+    pub max_chance: f32,
+    pub min_chance: f32,
+}
+
+impl Default for MyStratParams {
+    fn default() -> Self {
+        Self {
+            house_percent: 1.,
+            max_chance: 70.,
+            min_chance: 0.04,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct MyStrat {
+    #[serde(default)]
+    params: MyStratParams,
     high: bool,
     multiplier: f32,
-    max_chance: f32,
-    min_chance: f32,
     min_bet: f32,
     initial_bet: f32,
     chance: f32,
-    house_percent: f32,
     current_bet: f32,
     bank: f32,
     profit: f32,
@@ -19,19 +38,19 @@ pub struct MyStrat {
     win_streak: u32,
     loss_streak: u32,
     avg_losses_per_win: Vec<u32>,
+    #[serde(skip)]
+    capabilities: SiteCapabilities,
 }
 
 impl Default for MyStrat {
     fn default() -> Self {
         Self {
+            params: MyStratParams::default(),
             high: false,
             multiplier: 2.,
-            max_chance: 70.,
-            min_chance: 0.04,
             min_bet: 1e-8,
             initial_bet: 1e-8,
             chance: 50.,
-            house_percent: 1.,
             current_bet: 1e-8,
             bank: 1e-8,
             profit: 0.,
@@ -40,19 +59,27 @@ impl Default for MyStrat {
             win_streak: 0,
             loss_streak: 0,
             avg_losses_per_win: Vec::new(),
+            capabilities: SiteCapabilities::default(),
         }
     }
 }
 
 impl Strategy for MyStrat {
-    fn get_next_bet(&mut self, prediction: f32, _confidence: f32) -> (f32, f32, f32, bool) {
+    fn get_next_bet(&mut self, prediction: f32, _confidence: f32) -> BetDecision {
         self.high = prediction > 5000.;
 
-        let chance = (50. + self.house_percent) * (1. - ((prediction - 5000.).abs() / 5000.));
-        self.chance = chance.max(self.min_chance).min(self.max_chance);
+        let chance =
+            (50. + self.params.house_percent) * (1. - ((prediction - 5000.).abs() / 5000.));
+        self.chance = chance
+            .max(self.params.min_chance)
+            .min(self.params.max_chance);
 
         let mut base_multiplier = 1. / (chance / 100.);
-        base_multiplier = base_multiplier.clamp(1.02, 4750.0);
+        // Stakes are sized by `multiplier - 1`, keep clear of 1 whatever the site allows.
+        base_multiplier = self
+            .capabilities
+            .clamp_multiplier(base_multiplier)
+            .max(1.02);
 
         /*
         if self.bank / 1000. > self.min_bet {
@@ -71,10 +98,15 @@ impl Strategy for MyStrat {
 
         self.multiplier = base_multiplier;
 
-        (self.current_bet, self.multiplier, self.chance, self.high)
+        BetDecision {
+            amount: self.current_bet,
+            chance: self.chance,
+            multiplier: self.multiplier,
+            kind: BetKind::from_high(self.high),
+        }
     }
 
-    fn on_win(&mut self, bet_result: &BetResult) {
+    fn on_win(&mut self, bet_result: &BetOutcome) {
         self.loss = 0.;
         self.gain += bet_result.win_amount * 0.25;
         self.profit += bet_result.win_amount;
@@ -91,7 +123,7 @@ impl Strategy for MyStrat {
         self.loss = self.loss.max(0.);
     }
 
-    fn on_lose(&mut self, bet_result: &BetResult) {
+    fn on_lose(&mut self, bet_result: &BetOutcome) {
         self.profit -= bet_result.win_amount;
         self.bank -= bet_result.win_amount;
         self.gain -= bet_result.win_amount;
@@ -137,6 +169,17 @@ impl Strategy for MyStrat {
         self.bank = balance;
     }
 
+    fn set_capabilities(&mut self, capabilities: &SiteCapabilities) {
+        self.min_bet = capabilities.min_bet;
+        self.capabilities = capabilities.clone();
+    }
+
+    fn set_params(&mut self, params: toml::Table) -> Result<(), toml::de::Error> {
+        self.params = parse_params(params)?;
+
+        Ok(())
+    }
+
     fn get_profit(&self) -> f32 {
         self.profit
     }
@@ -148,4 +191,16 @@ impl Strategy for MyStrat {
         self.loss_streak = 0;
         self.win_streak = 0;
     }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        let params = std::mem::take(&mut self.params);
+        *self = serde_json::from_value(state)?;
+        self.params = params;
+
+        Ok(())
+    }
 }
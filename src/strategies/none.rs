@@ -1,13 +1,35 @@
-use crate::sites::BetResult;
-use crate::strategies::Strategy;
+use serde::{Deserialize, Serialize};
+
+use crate::sites::{BetDecision, BetKind, BetOutcome, SiteCapabilities};
+use crate::strategies::{parse_params, Strategy};
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NoStratParams {
+    pub house_percent: f32,
+    pub max_chance: f32,
+    pub min_chance: f32,
+    /// Bet on the predicted bucket of this many rolls instead of over/under, 0 to disable.
+    pub range_width: u32,
+}
 
-#[derive(Debug)]
+impl Default for NoStratParams {
+    fn default() -> Self {
+        Self {
+            house_percent: 1.,
+            max_chance: 50.,
+            min_chance: 0.02,
+            range_width: 0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct NoStrat {
+    #[serde(default)]
+    params: NoStratParams,
     multiplier: f32,
-    max_chance: f32,
-    min_chance: f32,
     chance: f32,
-    house_percent: f32,
     current_bet: f32,
     bank: f32,
     profit: f32,
@@ -21,26 +43,30 @@ pub struct NoStrat {
     loss: f32,
     initial_bank: f32,
     set_base_bet: bool,
+    #[serde(skip)]
+    capabilities: SiteCapabilities,
 }
 
 impl NoStrat {
     fn normal_strat(&mut self, prediction: f32, _confidence: f32) {
-        self.chance = (50. + self.house_percent) * (1. - ((prediction - 5000.).abs() / 5000.));
-        self.chance = self.chance.max(self.min_chance).min(self.max_chance);
+        self.chance =
+            (50. + self.params.house_percent) * (1. - ((prediction - 5000.).abs() / 5000.));
+        self.chance = self
+            .chance
+            .max(self.params.min_chance)
+            .min(self.params.max_chance);
         self.multiplier = 1. / (self.chance / 100.);
-        self.multiplier = self.multiplier.clamp(1.01, 4750.);
+        self.multiplier = self.capabilities.clamp_multiplier(self.multiplier);
     }
 }
 
 impl Default for NoStrat {
     fn default() -> Self {
         Self {
+            params: NoStratParams::default(),
             high: false,
             multiplier: 2.,
-            max_chance: 50.,
-            min_chance: 0.02,
             chance: 0.02,
-            house_percent: 1.,
             current_bet: 2e-8,
             bank: 1e-4,
             profit: 0.,
@@ -53,12 +79,13 @@ impl Default for NoStrat {
             loss: 0.,
             initial_bank: 0.,
             set_base_bet: false,
+            capabilities: SiteCapabilities::default(),
         }
     }
 }
 
 impl Strategy for NoStrat {
-    fn get_next_bet(&mut self, prediction: f32, confidence: f32) -> (f32, f32, f32, bool) {
+    fn get_next_bet(&mut self, prediction: f32, confidence: f32) -> BetDecision {
         self.high = prediction > 5000.;
         self.current_bet = self.current_bet.max(self.base_bet);
 
@@ -69,10 +96,21 @@ impl Strategy for NoStrat {
         }
         // self.current_bet = self.base_bet;
 
-        (self.current_bet, self.multiplier, self.chance, self.high)
+        let kind = if self.params.range_width > 0 {
+            BetKind::bucket(prediction, self.params.range_width)
+        } else {
+            BetKind::from_high(self.high)
+        };
+
+        BetDecision {
+            amount: self.current_bet,
+            chance: self.chance,
+            multiplier: self.multiplier,
+            kind,
+        }
     }
 
-    fn on_win(&mut self, bet_result: &BetResult) {
+    fn on_win(&mut self, bet_result: &BetOutcome) {
         self.spent -= bet_result.win_amount;
         self.spent = self.spent.max(0.);
         self.profit += bet_result.win_amount;
@@ -83,14 +121,14 @@ impl Strategy for NoStrat {
         self.loss = self.loss.max(0.);
 
         /*
-        self.chance = self.min_chance;
+        self.chance = self.params.min_chance;
         self.multiplier = 1. / (self.chance / 100.);
         self.multiplier = self.multiplier.clamp(1.01, 4750.);
         */
         self.current_bet += bet_result.win_amount * 0.25;
     }
 
-    fn on_lose(&mut self, bet_result: &BetResult) {
+    fn on_lose(&mut self, bet_result: &BetOutcome) {
         self.spent += bet_result.win_amount;
         self.profit -= bet_result.win_amount;
         self.bank -= bet_result.win_amount;
@@ -101,7 +139,7 @@ impl Strategy for NoStrat {
         self.current_bet = self.base_bet;
 
         /*
-        self.chance += self.min_chance;
+        self.chance += self.params.min_chance;
         self.multiplier = 1. / (self.chance / 100.);
         self.multiplier = self.multiplier.clamp(1.01, 4750.);
         self.current_bet += self.current_bet / (self.multiplier - 1.15);
@@ -130,6 +168,18 @@ impl Strategy for NoStrat {
         self.win_target = balance;
     }
 
+    fn set_capabilities(&mut self, capabilities: &SiteCapabilities) {
+        self.base_min_bet = capabilities.min_bet;
+        self.base_bet = self.base_bet.max(capabilities.min_bet);
+        self.capabilities = capabilities.clone();
+    }
+
+    fn set_params(&mut self, params: toml::Table) -> Result<(), toml::de::Error> {
+        self.params = parse_params(params)?;
+
+        Ok(())
+    }
+
     fn get_profit(&self) -> f32 {
         self.profit
     }
@@ -168,4 +218,16 @@ impl Strategy for NoStrat {
 
         self
     }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        let params = std::mem::take(&mut self.params);
+        *self = serde_json::from_value(state)?;
+        self.params = params;
+
+        Ok(())
+    }
 }
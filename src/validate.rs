@@ -0,0 +1,444 @@
+use std::fmt;
+
+use crate::config::{
+    EffectiveSession, TomlConfig, CRYPTO_GAMES_API_KEY, DUCK_DICE_API_KEY, FREEBITCOIN_PASSWORD,
+    WINDICE_API_KEY,
+};
+use crate::currency::Currency;
+use crate::sites::{crypto_games, windice, Sites};
+use crate::strategies;
+
+/// A single problem in the config, `path` points at the offending key.
+#[derive(Debug)]
+pub struct ConfigIssue {
+    pub path: String,
+    pub message: String,
+    pub hint: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}\n    hint: {}",
+            self.path, self.message, self.hint
+        )
+    }
+}
+
+/// Name of the site's section in config.toml.
+fn section(site: Sites) -> &'static str {
+    match site {
+        Sites::CryptoGames => "crypto_games",
+        Sites::FreeBitcoIn => "freebitcoin",
+        Sites::DuckDiceIo => "duck_dice",
+        Sites::Windice => "windice",
+    }
+}
+
+#[derive(Default)]
+struct Issues(Vec<ConfigIssue>);
+
+impl Issues {
+    fn push(
+        &mut self,
+        path: impl Into<String>,
+        message: impl Into<String>,
+        hint: impl Into<String>,
+    ) {
+        self.0.push(ConfigIssue {
+            path: path.into(),
+            message: message.into(),
+            hint: hint.into(),
+        });
+    }
+}
+
+/// Checks the whole config and returns every problem found. Paper sessions still log in, so
+/// credentials are required for every site in use.
+pub fn validate(config: &TomlConfig) -> Vec<ConfigIssue> {
+    let mut issues = Issues::default();
+
+    check_limits(config, &mut issues);
+    check_reconcile(config, &mut issues);
+
+    if config.sessions.is_empty() {
+        match config.active_site() {
+            Ok(site) => check_site(config, site, &mut issues),
+            Err(err) => issues.push(
+                "crypto_games.enabled, freebitcoin.enabled, duck_dice.enabled, windice.enabled",
+                err.to_string(),
+                "set `enabled = true` on exactly one site, pass --site, or add [[sessions]]",
+            ),
+        }
+    }
+
+    for (index, session) in config.sessions.iter().enumerate() {
+        let path = format!("sessions[{index}]");
+
+        if config.sessions[..index]
+            .iter()
+            .any(|other| other.name == session.name)
+        {
+            issues.push(
+                format!("{path}.name"),
+                format!("duplicate session name {:?}", session.name),
+                "session names key the saved state and the logs, give each one a unique name",
+            );
+        }
+        if let Some(currency) = &session.currency {
+            check_currency(
+                session.site,
+                currency,
+                &format!("{path}.currency"),
+                &mut issues,
+            );
+        }
+        if session.strategy.is_some() || session.strategy_params.is_some() {
+            let site_config = config.for_session(session);
+            let (strategy, params) = site_config.strategy(session.site);
+            check_strategy_params(
+                session.site,
+                strategy,
+                params,
+                &format!("{path}.strategy_params"),
+                &mut issues,
+            );
+        }
+    }
+
+    let mut checked = Vec::new();
+    for session in &config.sessions {
+        if !checked.contains(&session.site) {
+            checked.push(session.site);
+            check_site(config, session.site, &mut issues);
+        }
+    }
+
+    issues.0
+}
+
+fn check_limits(config: &TomlConfig, issues: &mut Issues) {
+    let limits = &config.runner.limits;
+
+    if let Some(stop_loss) = limits.stop_loss.filter(|stop_loss| *stop_loss <= 0.) {
+        issues.push(
+            "runner.limits.stop_loss",
+            format!("stop_loss is {stop_loss}, every session would stop after its first bet"),
+            "stop_loss is the loss to stop at, set it to a positive amount",
+        );
+    }
+    if let Some(take_profit) = limits.take_profit.filter(|take_profit| *take_profit <= 0.) {
+        issues.push(
+            "runner.limits.take_profit",
+            format!("take_profit is {take_profit}, every session would stop after its first bet"),
+            "set take_profit to a positive amount or remove it",
+        );
+    }
+    if limits.max_loss_streak == Some(0) {
+        issues.push(
+            "runner.limits.max_loss_streak",
+            "max_loss_streak is 0, every session would stop after its first bet",
+            "set it to 1 or more, or remove it",
+        );
+    }
+}
+
+fn check_reconcile(config: &TomlConfig, issues: &mut Issues) {
+    let reconcile = &config.runner.reconcile;
+
+    if reconcile.every == Some(0) {
+        issues.push(
+            "runner.reconcile.every",
+            "every is 0, sessions would never bet between reconciliations",
+            "set it to 1 or more, or remove it to turn reconciliation off",
+        );
+    }
+    if let Some(max_drift) = reconcile.max_drift.filter(|max_drift| *max_drift <= 0.) {
+        issues.push(
+            "runner.reconcile.max_drift",
+            format!("max_drift is {max_drift}, every reconciliation would flag drift"),
+            "set max_drift to a positive amount or remove it",
+        );
+    }
+    if reconcile.pause_on_drift && (reconcile.every.is_none() || reconcile.max_drift.is_none()) {
+        issues.push(
+            "runner.reconcile.pause_on_drift",
+            "pause_on_drift needs both every and max_drift",
+            "set every and max_drift, or remove pause_on_drift",
+        );
+    }
+}
+
+fn check_currency(site: Sites, currency: &Currency, path: &str, issues: &mut Issues) {
+    let supported = match site {
+        Sites::CryptoGames => crypto_games::Currency::try_from(currency).is_ok(),
+        Sites::FreeBitcoIn => *currency == Currency::BTC,
+        Sites::Windice => windice::Currency::try_from(currency).is_ok(),
+        Sites::DuckDiceIo => true,
+    };
+
+    if !supported {
+        let hint = match site {
+            Sites::CryptoGames => {
+                "crypto.games supports BTC, ETH, USDT, SOL, XRP, BNB, USDC, DOGE, SHIB, LTC, BCH, ETC and POL"
+            }
+            Sites::Windice => {
+                "Windice supports BTC, ETH, LTC, DOGE, BCH, XRP, TRX, USDT and BNB"
+            }
+            _ => "freebitco.in only supports BTC",
+        };
+        issues.push(
+            path,
+            format!("{currency} is not supported by {site:?}"),
+            hint,
+        );
+    }
+}
+
+/// Checks the params a session runs with, after any `[[sessions]]` override.
+fn check_strategy_params(
+    site: Sites,
+    strategy: crate::config::TomlStrategies,
+    params: &toml::Table,
+    path: &str,
+    issues: &mut Issues,
+) {
+    let resolved = match strategies::resolve_params(strategy, params.clone()) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            issues.push(
+                path,
+                format!("invalid params for {strategy:?}: {}", err.message()),
+                "remove the key or check its spelling and type, `inspect-config` lists the defaults",
+            );
+            return;
+        }
+    };
+
+    let chance = |key: &str| resolved.get(key).and_then(toml::Value::as_float);
+    if let (Some(min_chance), Some(max_chance)) = (chance("min_chance"), chance("max_chance")) {
+        if min_chance > max_chance {
+            issues.push(
+                path,
+                format!("min_chance {min_chance} is above max_chance {max_chance}"),
+                "lower min_chance or raise max_chance",
+            );
+        }
+    }
+    let range_width = resolved
+        .get("range_width")
+        .and_then(toml::Value::as_integer);
+    if range_width.is_some_and(|range_width| range_width > 0) && !site.ranges() {
+        issues.push(
+            format!("{path}.range_width"),
+            format!("{site:?} only takes over/under bets"),
+            "remove range_width or bet on Windice",
+        );
+    }
+}
+
+fn check_site(config: &TomlConfig, site: Sites, issues: &mut Issues) {
+    let section = section(site);
+
+    match site {
+        Sites::CryptoGames => {
+            check_currency(
+                site,
+                &config.crypto_games.currency,
+                &format!("{section}.currency"),
+                issues,
+            );
+            if config.crypto_games.api_key.is_empty() {
+                issues.push(
+                    format!("{section}.api_key"),
+                    "no API key set",
+                    format!("set {CRYPTO_GAMES_API_KEY} in the environment or .env, or run `credentials set {CRYPTO_GAMES_API_KEY}`"),
+                );
+            }
+        }
+        Sites::FreeBitcoIn => {
+            if config.freebitcoin.btc_address.is_empty() {
+                issues.push(
+                    format!("{section}.btc_address"),
+                    "no BTC address set",
+                    "set btc_address to the address you log in with",
+                );
+            }
+            if config.freebitcoin.password.is_empty() {
+                issues.push(
+                    format!("{section}.password"),
+                    "no password set",
+                    format!("set {FREEBITCOIN_PASSWORD} in the environment or .env, or run `credentials set {FREEBITCOIN_PASSWORD}`"),
+                );
+            }
+        }
+        Sites::DuckDiceIo => {
+            if config.duck_dice.api_key.is_empty() {
+                issues.push(
+                    format!("{section}.api_key"),
+                    "no API key set",
+                    format!("set {DUCK_DICE_API_KEY} in the environment or .env, or run `credentials set {DUCK_DICE_API_KEY}`"),
+                );
+            }
+        }
+        Sites::Windice => {
+            check_currency(
+                site,
+                &config.windice.currency,
+                &format!("{section}.currency"),
+                issues,
+            );
+            if config.windice.api_key.is_empty() {
+                issues.push(
+                    format!("{section}.api_key"),
+                    "no API key set",
+                    format!("set {WINDICE_API_KEY} in the environment or .env, or run `credentials set {WINDICE_API_KEY}`"),
+                );
+            }
+        }
+    }
+
+    let (strategy, params) = config.strategy(site);
+    check_strategy_params(
+        site,
+        strategy,
+        params,
+        &format!("{section}.strategy_params"),
+        issues,
+    );
+}
+
+/// Renders the config each session actually runs with as TOML, secrets stay redacted.
+pub fn effective_toml(config: &TomlConfig, sessions: &[EffectiveSession]) -> String {
+    let mut root = toml::Table::new();
+    if let Ok(runner) = toml::Table::try_from(&config.runner) {
+        root.insert("runner".to_string(), toml::Value::Table(runner));
+    }
+
+    let sessions = sessions
+        .iter()
+        .map(|session| {
+            let site_config = &session.config;
+            let (strategy, params) = site_config.strategy(session.site);
+            let mut table = toml::Table::new();
+            let mut insert = |key: &str, value: String| {
+                table.insert(key.to_string(), toml::Value::String(value));
+            };
+
+            insert("name", session.name.clone());
+            insert("site", format!("{:?}", session.site));
+            if let Some(currency) = site_config.currency() {
+                insert("currency", currency.to_string());
+            }
+            insert("strategy", format!("{strategy:?}"));
+            match session.site {
+                Sites::CryptoGames => {
+                    insert("api_key", site_config.crypto_games.api_key.to_string())
+                }
+                Sites::FreeBitcoIn => {
+                    insert("btc_address", site_config.freebitcoin.btc_address.clone());
+                    insert("password", site_config.freebitcoin.password.to_string());
+                }
+                Sites::DuckDiceIo => insert("api_key", site_config.duck_dice.api_key.to_string()),
+                Sites::Windice => insert("api_key", site_config.windice.api_key.to_string()),
+            }
+            table.insert("paper".to_string(), toml::Value::Boolean(session.paper));
+            table.insert(
+                "strategy_params".to_string(),
+                toml::Value::Table(
+                    strategies::resolve_params(strategy, params.clone())
+                        .unwrap_or_else(|_| params.clone()),
+                ),
+            );
+
+            toml::Value::Table(table)
+        })
+        .collect();
+    root.insert("sessions".to_string(), toml::Value::Array(sessions));
+
+    toml::to_string_pretty(&root).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITES: &str = r#"
+        [crypto_games]
+        enabled = false
+        api_key = "key"
+        currency = "BTC"
+        strategy = "None"
+
+        [freebitcoin]
+        enabled = false
+        btc_address = "address"
+        password = "password"
+        strategy = "None"
+
+        [duck_dice]
+        enabled = true
+        api_key = "key"
+        currency = "BTC"
+        strategy = "None"
+
+        [windice]
+        enabled = false
+        api_key = "key"
+        currency = "BTC"
+        strategy = "None"
+    "#;
+
+    fn paths(config: &str) -> Vec<String> {
+        let config: TomlConfig = toml::from_str(&format!("{SITES}\n{config}")).unwrap();
+
+        validate(&config)
+            .into_iter()
+            .map(|issue| issue.path)
+            .collect()
+    }
+
+    #[test]
+    fn valid_config_has_no_issues() {
+        assert!(paths("").is_empty());
+        assert!(paths(
+            r#"
+            [[sessions]]
+            name = "ranges"
+            site = "Windice"
+            strategy_params = { range_width = 100 }
+            "#
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn session_overrides_are_checked_as_merged() {
+        let paths = paths(
+            r#"
+            [runner.limits]
+            max_loss_streak = 0
+
+            [[sessions]]
+            name = "duck"
+            site = "DuckDiceIo"
+            strategy_params = { range_width = 100 }
+
+            [[sessions]]
+            name = "duck"
+            site = "DuckDiceIo"
+            strategy_params = { min_chance = 60.0 }
+            "#,
+        );
+        assert_eq!(
+            paths,
+            [
+                "runner.limits.max_loss_streak",
+                "sessions[0].strategy_params.range_width",
+                "sessions[1].name",
+                "sessions[1].strategy_params",
+            ]
+        );
+    }
+}